@@ -0,0 +1,161 @@
+//! Compares states and packed states as keys of the searched states, and measures the search
+//! throughput of A* and of the in-place depth-first search.
+//!
+//! Run with `cargo bench --bench state_encoding`. The memory taken by each kind of key is printed
+//! before the timings.
+
+use std::{collections::VecDeque, hint::black_box};
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group};
+use indexmap::IndexSet;
+use rustc_hash::{FxBuildHasher, FxHashSet};
+use solitaire_solver::{
+    deck_generator::generate_game,
+    packed::CompactPackedState,
+    solver::{Budget, Heuristic, SearchStrategy, SolverOptions, solve_with},
+    state::State,
+};
+
+const SEED: &str = "_3662067111875154814";
+const SAMPLE_SIZE: usize = 20_000;
+
+fn start() -> State {
+    generate_game(SEED.parse().unwrap()).normalize()
+}
+
+/// The first distinct states found breadth-first from the start of the deal.
+fn sample_states() -> Vec<State> {
+    let start = start();
+    let mut seen = FxHashSet::from_iter([start.clone()]);
+    let mut queue = VecDeque::from([start]);
+    let mut states = vec![];
+    while let Some(state) = queue.pop_front()
+        && states.len() < SAMPLE_SIZE
+    {
+        for (_, successor) in state.get_next_states() {
+            if seen.insert(successor.clone()) {
+                queue.push_back(successor);
+            }
+        }
+        states.push(state);
+    }
+    states
+}
+
+fn report_memory(states: &[State]) {
+    let state_bytes =
+        states.iter().map(State::approximate_size).sum::<usize>() as f64 / states.len() as f64;
+    let packed_bytes = size_of_val(&states[0].pack());
+    let compact_bytes = size_of::<CompactPackedState>();
+    println!("memory per stored key over {} states:", states.len());
+    println!("  State              ~{state_bytes:.0} bytes");
+    println!("  PackedState         {packed_bytes} bytes");
+    println!("  CompactPackedState  {compact_bytes} bytes");
+}
+
+fn closed_set(c: &mut Criterion) {
+    let states = sample_states();
+    report_memory(&states);
+
+    let mut group = c.benchmark_group("closed set insertion");
+    group.throughput(Throughput::Elements(states.len() as u64));
+    group.bench_function("State", |b| {
+        b.iter_batched(
+            || states.clone(),
+            |states| {
+                let mut closed = IndexSet::<State, FxBuildHasher>::default();
+                for state in states {
+                    closed.insert(state);
+                }
+                closed
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("PackedState", |b| {
+        b.iter(|| {
+            let mut closed = IndexSet::<_, FxBuildHasher>::default();
+            for state in &states {
+                closed.insert(state.pack());
+            }
+            closed
+        })
+    });
+    group.bench_function("CompactPackedState", |b| {
+        b.iter(|| {
+            let mut closed = IndexSet::<CompactPackedState, FxBuildHasher>::default();
+            for state in &states {
+                closed.insert(state.pack_as());
+            }
+            closed
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("packing");
+    group.throughput(Throughput::Elements(states.len() as u64));
+    group.bench_function("pack", |b| {
+        b.iter(|| {
+            for state in &states {
+                black_box(state.pack());
+            }
+        })
+    });
+    let packed = states.iter().map(State::pack).collect::<Vec<_>>();
+    group.bench_function("unpack", |b| {
+        b.iter(|| {
+            for state in &packed {
+                black_box(state.unpack());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    const EXPANDED_NODES: usize = 20_000;
+    let start = start();
+    let options = SolverOptions {
+        heuristic: Heuristic::Admissible,
+        budget: Budget {
+            expanded_nodes: Some(EXPANDED_NODES),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let mut group = c.benchmark_group("search");
+    group.throughput(Throughput::Elements(EXPANDED_NODES as u64));
+    group.sample_size(10);
+    group.bench_function("A* expanded states", |b| {
+        b.iter(|| solve_with(&start, &options))
+    });
+
+    let deals = (1..=40)
+        .map(|seed| generate_game(seed.to_string().parse().unwrap()).normalize())
+        .collect::<Vec<_>>();
+    let options = SolverOptions {
+        strategy: SearchStrategy::DepthFirst,
+        ..Default::default()
+    };
+    let expanded_nodes = deals
+        .iter()
+        .map(|deal| solve_with(deal, &options).statistics.expanded_nodes)
+        .sum::<usize>();
+    group.throughput(Throughput::Elements(expanded_nodes as u64));
+    group.bench_function("depth-first expanded states over 40 deals", |b| {
+        b.iter(|| {
+            for deal in &deals {
+                solve_with(deal, &options);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, closed_set, search);
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
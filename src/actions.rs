@@ -1,24 +1,132 @@
-use itertools::{Either, Itertools};
+//! Moves of the game and how they are played. Which moves are legal is defined by
+//! [`rules`](crate::rules).
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GameConfig,
+    rules,
+    state::{Card, CardOrBundle, CardStack, PlaceHolder, State},
+};
+
+/// Where a single card is taken from.
+///
+/// Stacks and placeholders are referred to by their position in the iteration order of the
+/// [`State`] the move was generated from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardSource {
+    Stack(usize),
+    PlaceHolder(usize),
+}
+
+/// A single move of the game.
+///
+/// Stacks and placeholders are referred to by their position in the iteration order of the
+/// [`State`] the move was generated from, so a move is only meaningful for that state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Move {
+    /// Sends the exposed card at `from` to the [`Output`].
+    Output { from: CardSource },
+    /// Moves the top `count` cards of a stack onto another stack.
+    StackToStack {
+        from: usize,
+        to: usize,
+        count: usize,
+    },
+    /// Moves the exposed card of a stack into an empty placeholder.
+    StackToPlaceHolder { from: usize, to: usize },
+    /// Moves the card held in a placeholder onto a stack.
+    PlaceHolderToStack { from: usize, to: usize },
+    /// Bundles an exposed beast with two exposed pirates into the placeholder `into`.
+    PromoteBeast {
+        beast: CardSource,
+        pirates: [CardSource; 2],
+        into: usize,
+    },
+}
 
-use crate::state::{Card, CardOrBundle, CardStack, NormalCard, Output, PlaceHolder, State};
+impl CardSource {
+    fn map_indices(self, stack: impl Fn(usize) -> usize, slot: impl Fn(usize) -> usize) -> Self {
+        match self {
+            CardSource::Stack(index) => CardSource::Stack(stack(index)),
+            CardSource::PlaceHolder(index) => CardSource::PlaceHolder(slot(index)),
+        }
+    }
+}
 
-#[derive(Clone, Copy)]
-enum CardLocation<'a> {
-    Stack(&'a CardStack),
-    PlaceHolder(&'a PlaceHolder),
+impl Move {
+    /// Rewrites the stack and placeholder indices of this move, e.g. to refer to another
+    /// ordering of the same state.
+    pub fn map_indices(
+        self,
+        stack: impl Fn(usize) -> usize,
+        slot: impl Fn(usize) -> usize,
+    ) -> Self {
+        match self {
+            Move::Output { from } => Move::Output {
+                from: from.map_indices(&stack, &slot),
+            },
+            Move::StackToStack { from, to, count } => Move::StackToStack {
+                from: stack(from),
+                to: stack(to),
+                count,
+            },
+            Move::StackToPlaceHolder { from, to } => Move::StackToPlaceHolder {
+                from: stack(from),
+                to: slot(to),
+            },
+            Move::PlaceHolderToStack { from, to } => Move::PlaceHolderToStack {
+                from: slot(from),
+                to: stack(to),
+            },
+            Move::PromoteBeast {
+                beast,
+                pirates,
+                into,
+            } => Move::PromoteBeast {
+                beast: beast.map_indices(&stack, &slot),
+                pirates: pirates.map(|pirate| pirate.map_indices(&stack, &slot)),
+                into: slot(into),
+            },
+        }
+    }
 }
 
 impl State {
-    fn pop(&mut self, location: &CardLocation<'_>) -> Option<Card> {
+    fn stack_at(&self, index: usize) -> &CardStack {
+        self.board
+            .iter()
+            .nth(index)
+            .expect("stack index out of range")
+    }
+
+    fn placeholder_at(&self, index: usize) -> &PlaceHolder {
+        self.placeholders
+            .holes
+            .iter()
+            .nth(index)
+            .expect("placeholder index out of range")
+    }
+
+    fn resolve(&self, source: &CardSource) -> Location {
+        match *source {
+            CardSource::Stack(index) => Location::Stack(self.stack_at(index).clone()),
+            CardSource::PlaceHolder(index) => Location::PlaceHolder(*self.placeholder_at(index)),
+        }
+    }
+
+    fn pop(&mut self, location: &Location) -> Option<Card> {
         match location {
-            CardLocation::Stack(stack) => {
+            Location::Stack(stack) => {
                 let mut stack_from = self.board.take(stack).unwrap();
                 let card = stack_from.cards.pop();
                 self.board.insert(stack_from);
 
                 card
             }
-            CardLocation::PlaceHolder(hole) => {
+            Location::PlaceHolder(hole) => {
                 let mut hole_from = self.placeholders.holes.take(hole).unwrap();
                 let card = match hole_from.0.take() {
                     Some(CardOrBundle::Card(card)) => Some(card),
@@ -30,283 +138,106 @@ impl State {
             }
         }
     }
-}
-
-enum CardSplitOrigin<'a> {
-    Stack {
-        location: &'a CardStack,
-        cards: &'a [Card],
-    },
-    PlaceHolder {
-        location: &'a PlaceHolder,
-        card: &'a Card,
-    },
-}
-
-impl Output {
-    fn can_output_card(&self, NormalCard { number, color }: &NormalCard) -> bool {
-        &self[color].next() == number
-    }
-}
-
-impl Card {
-    fn can_stack_with(&self, card_to_place: &Card) -> bool {
-        self.normal()
-            .zip(card_to_place.normal())
-            .is_some_and(|(card, card_to_place)| {
-                card.number == card_to_place.number.next() && card.color != card_to_place.color
-            })
-    }
-}
 
-impl CardStack {
-    fn can_stack_with(&self, card_to_place: &Card) -> bool {
-        self.cards
-            .last()
-            .is_none_or(|card| card.can_stack_with(card_to_place))
+    fn push(&mut self, stack: &CardStack, cards: &[Card]) {
+        let mut stack = self.board.take(stack).unwrap();
+        stack.cards.extend_from_slice(cards);
+        self.board.insert(stack);
     }
-}
 
-impl State {
-    fn valid_card_outputs(&self) -> impl Iterator<Item = (CardLocation<'_>, &'_ NormalCard)> {
-        self.board
-            .iter()
-            .filter_map(move |stack| {
-                stack
-                    .cards
-                    .last()
-                    .and_then(|card| card.normal())
-                    .and_then(move |card| {
-                        self.output
-                            .can_output_card(card)
-                            .then_some((CardLocation::Stack(stack), card))
-                    })
-            })
-            .chain(self.placeholders.holes.iter().filter_map(|hole| {
-                if let Some(CardOrBundle::Card(Card::Normal(ref card))) = hole.0
-                    && self.output.can_output_card(card)
-                {
-                    Some((CardLocation::PlaceHolder(hole), card))
-                } else {
-                    None
-                }
-            }))
+    fn fill(&mut self, hole: &PlaceHolder, content: CardOrBundle) {
+        let mut hole = self.placeholders.holes.take(hole).unwrap();
+        assert!(hole.0.is_none(), "placeholder should be empty");
+        hole.0 = Some(content);
+        self.placeholders.holes.insert(hole);
     }
 
-    fn valid_card_splits(&self) -> impl Iterator<Item = CardSplitOrigin<'_>> {
-        self.board
-            .iter()
-            .flat_map(|stack| {
-                let max_split_size = match stack.cards.len() {
-                    0 => 0,
-                    _ => {
-                        stack
-                            .cards
-                            .iter()
-                            .rev()
-                            .skip(1)
-                            .zip(stack.cards.iter().rev())
-                            .take_while(|(prev, top)| prev.can_stack_with(top))
-                            .count()
-                            + 1
-                    }
+    /// Plays `mv`, which must have been generated from this same state.
+    pub fn apply(&mut self, mv: &Move) {
+        match mv {
+            Move::Output { from } => {
+                let from = self.resolve(from);
+                let Some(Card::Normal(card)) = self.pop(&from) else {
+                    panic!("only numbered cards can be sent to the output");
                 };
-                (1..=max_split_size).map(|split_size| CardSplitOrigin::Stack {
-                    location: stack,
-                    cards: &stack.cards[stack.cards.len() - split_size..],
-                })
-            })
-            .chain(self.placeholders.holes.iter().flat_map(|hole| {
-                hole.0.as_ref().and_then(|card_or_bundle| {
-                    if let CardOrBundle::Card(card) = card_or_bundle {
-                        Some(CardSplitOrigin::PlaceHolder {
-                            location: hole,
-                            card,
-                        })
-                    } else {
-                        None
-                    }
-                })
-            }))
-    }
-
-    fn exposed_beasts(&self) -> impl Iterator<Item = CardLocation<'_>> {
-        self.board
-            .iter()
-            .flat_map(|stack| {
-                stack
-                    .cards
-                    .last()
-                    .is_some_and(|card| matches!(card, Card::Beast(_)))
-                    .then_some(CardLocation::Stack(stack))
-            })
-            .chain(self.placeholders.holes.iter().flat_map(|hole| {
-                hole.0
-                    .as_ref()
-                    .is_some_and(|card| matches!(card, CardOrBundle::Card(Card::Beast(_))))
-                    .then_some(CardLocation::PlaceHolder(hole))
-            }))
-    }
-
-    fn exposed_pirates(&self) -> impl Iterator<Item = CardLocation<'_>> {
-        self.board
-            .iter()
-            .flat_map(|stack| {
-                stack
-                    .cards
-                    .last()
-                    .is_some_and(|card| matches!(card, Card::Pirate))
-                    .then_some(CardLocation::Stack(stack))
-            })
-            .chain(self.placeholders.holes.iter().flat_map(|hole| {
-                hole.0
-                    .as_ref()
-                    .is_some_and(|card| matches!(card, CardOrBundle::Card(Card::Pirate)))
-                    .then_some(CardLocation::PlaceHolder(hole))
-            }))
-    }
-
-    pub fn get_next_states(&self) -> impl Iterator<Item = State> + '_ {
-        let card_output_states = self.valid_card_outputs().map(|(card_location, card)| {
-            let mut new_state = self.clone();
-            match card_location {
-                CardLocation::PlaceHolder(placeholder) => {
-                    new_state.placeholders.holes.remove(placeholder);
-                }
-                CardLocation::Stack(card_stack) => {
-                    let mut new_stack = new_state.board.take(card_stack).unwrap();
-                    new_stack.cards.pop();
-                    new_state.board.insert(new_stack);
-                }
+                assert!(self.output.can_output_card(&card));
+                self.output[&card.color].next_inplace();
             }
-            new_state.output[&card.color].next_inplace();
-            new_state
-        });
-
-        let card_split_states =
-            self.valid_card_splits()
-                .flat_map(move |split_origin| match split_origin {
-                    CardSplitOrigin::PlaceHolder { location, card } => {
-                        Either::Left(self.board.iter().flat_map(|stack| {
-                            stack.can_stack_with(card).then(|| {
-                                let mut new_state = self.clone();
-
-                                // remove from placeholder
-                                let mut hole = new_state.placeholders.holes.take(location).unwrap();
-                                hole.0 = None;
-                                new_state.placeholders.holes.insert(hole);
-
-                                // add to board stack
-                                let mut stack = new_state.board.take(stack).unwrap();
-                                stack.cards.push(*card);
-                                new_state.board.insert(stack);
+            Move::StackToStack { from, to, count } => {
+                let from = self.stack_at(*from).clone();
+                let to = self.stack_at(*to).clone();
 
-                                new_state
-                            })
-                        }))
-                    }
-                    CardSplitOrigin::Stack { location, cards } => {
-                        Either::Right(self.board.iter().flat_map(move |stack| {
-                            stack.can_stack_with(cards.first().unwrap()).then(|| {
-                                let mut new_state = self.clone();
-
-                                // remove from stack
-                                let mut from_stack = new_state.board.take(location).unwrap();
-                                let drained_cards = from_stack
-                                    .cards
-                                    .drain(from_stack.cards.len() - cards.len()..);
-                                assert!(drained_cards.as_ref().eq(cards));
-                                drop(drained_cards);
-                                new_state.board.insert(from_stack);
-
-                                // add to board stack
-                                let mut stack = new_state.board.take(stack).unwrap();
-                                stack.cards.extend_from_slice(cards);
-                                new_state.board.insert(stack);
-
-                                new_state
-                            })
-                        }))
-                    }
-                });
-
-        let card_hold_states = self
-            .board
-            .iter()
-            .filter(|stack| !stack.cards.is_empty())
-            .flat_map(|stack| {
-                self.placeholders
-                    .holes
-                    .iter()
-                    .filter(|hole| hole.0.is_none())
-                    .map(move |hole| (stack, hole))
-            })
-            .map(|(stack, hole)| {
-                let mut new_state = self.clone();
+                let mut from_stack = self.board.take(&from).unwrap();
+                let cards = from_stack.cards.split_off(from_stack.cards.len() - count);
+                self.board.insert(from_stack);
 
-                let mut stack_from = new_state.board.take(stack).unwrap();
-                let card = stack_from.cards.pop().unwrap();
-                new_state.board.insert(stack_from);
-
-                let mut hole_into = new_state.placeholders.holes.take(hole).unwrap();
-                hole_into.0 = Some(CardOrBundle::Card(card));
-                new_state.placeholders.holes.insert(hole_into);
-
-                new_state
-            });
+                self.push(&to, &cards);
+            }
+            Move::StackToPlaceHolder { from, to } => {
+                let from = Location::Stack(self.stack_at(*from).clone());
+                let to = *self.placeholder_at(*to);
 
-        let free_placeholder = self.placeholders.holes.iter().find(|hole| hole.0.is_none());
+                let card = self.pop(&from).unwrap();
+                self.fill(&to, CardOrBundle::Card(card));
+            }
+            Move::PlaceHolderToStack { from, to } => {
+                let from = Location::PlaceHolder(*self.placeholder_at(*from));
+                let to = self.stack_at(*to).clone();
 
-        let beast_promotion_states = self
-            .exposed_beasts()
-            .cartesian_product(
-                self.exposed_pirates()
-                    .collect_vec()
-                    .into_iter()
-                    .tuple_combinations(),
-            )
-            .flat_map(move |(beast_location, (pirate1, pirate2))| {
-                match (beast_location, free_placeholder) {
-                    (CardLocation::Stack(_stack), Some(free_placeholder)) => {
-                        let mut new_state = self.clone();
-                        let Card::Beast(beast) = new_state.pop(&beast_location).unwrap() else {
-                            unreachable!("should be a beast");
-                        };
-                        let pirate1 = new_state.pop(&pirate1).unwrap();
-                        assert_eq!(pirate1, Card::Pirate);
-                        let pirate2 = new_state.pop(&pirate2).unwrap();
-                        assert_eq!(pirate2, Card::Pirate);
+                let card = self.pop(&from).unwrap();
+                self.push(&to, &[card]);
+            }
+            Move::PromoteBeast {
+                beast,
+                pirates,
+                into,
+            } => {
+                // the bundle can go into the slot the beast or one of the pirates leaves, which is
+                // an empty one once they are taken, as slots are only told apart by their content
+                let into =
+                    if [*beast, pirates[0], pirates[1]].contains(&CardSource::PlaceHolder(*into)) {
+                        PlaceHolder::default()
+                    } else {
+                        *self.placeholder_at(*into)
+                    };
+                let beast = self.resolve(beast);
+                let pirates = pirates.map(|pirate| self.resolve(&pirate));
 
-                        let mut bundle_place =
-                            new_state.placeholders.holes.take(free_placeholder).unwrap();
-                        bundle_place.0 = Some(CardOrBundle::BeastBundle(beast));
-                        new_state.placeholders.holes.insert(bundle_place);
+                let Some(Card::Beast(beast_card)) = self.pop(&beast) else {
+                    panic!("should be a beast");
+                };
+                for pirate in &pirates {
+                    assert_eq!(self.pop(pirate), Some(Card::Pirate));
+                }
+                self.fill(&into, CardOrBundle::BeastBundle(beast_card));
+            }
+        }
+    }
+}
 
-                        Some(new_state)
-                    }
-                    (CardLocation::PlaceHolder(_hole), _) => {
-                        let mut new_state = self.clone();
-                        let Card::Beast(beast) = new_state.pop(&beast_location).unwrap() else {
-                            unreachable!("should be a beast");
-                        };
-                        let pirate1 = new_state.pop(&pirate1).unwrap();
-                        assert_eq!(pirate1, Card::Pirate);
-                        let pirate2 = new_state.pop(&pirate2).unwrap();
-                        assert_eq!(pirate2, Card::Pirate);
+enum Location {
+    Stack(CardStack),
+    PlaceHolder(PlaceHolder),
+}
 
-                        let mut bundle_place = new_state.placeholders.holes.take(&PlaceHolder::default()).unwrap();
-                        bundle_place.0 = Some(CardOrBundle::BeastBundle(beast));
-                        new_state.placeholders.holes.insert(bundle_place);
+impl State {
+    /// Every legal move from this state.
+    pub fn legal_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        rules::legal_moves(self)
+    }
 
-                        Some(new_state)
-                    }
-                    _ => None,
-                }
-            });
+    /// An output move that can never make the game harder, if any, so it can be played without
+    /// considering the alternatives. `game` is the variant this state belongs to.
+    pub fn safe_output(&self, game: &GameConfig) -> Option<Move> {
+        rules::safe_output(self, game)
+    }
 
-        card_output_states
-            .chain(card_split_states)
-            .chain(card_hold_states)
-            .chain(beast_promotion_states)
+    /// Every state reachable from this one with a single legal move, along with that move.
+    pub fn get_next_states(&self) -> impl Iterator<Item = (Move, State)> + '_ {
+        self.legal_moves().map(|mv| {
+            let mut new_state = self.clone();
+            new_state.apply(&mv);
+            (mv, new_state)
+        })
     }
 }
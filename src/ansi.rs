@@ -8,6 +8,7 @@ pub enum AnsiSequence {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Color {
     Red = 31,
+    Green = 32,
     Blue = 34,
     Cyan = 36,
     White = 37,
@@ -48,7 +49,7 @@ impl Display for Matrix {
             for (color, char) in line {
                 if prev_color.is_none_or(|prev_color| color != prev_color) {
                     AnsiSequence::Reset.fmt(f)?;
-                    color.fmt(f)?;
+                    AnsiSequence::Color(*color).fmt(f)?;
                 }
                 f.write_char(*char)?;
                 prev_color = Some(color);
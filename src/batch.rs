@@ -0,0 +1,310 @@
+//! Solving many deals in parallel, writing one record per deal to a resumable results file.
+
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Read, Seek, Write},
+    ops::RangeInclusive,
+    path::Path,
+    str::FromStr,
+};
+
+use anyhow::{Context, anyhow};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GameConfig,
+    deck_generator::generate_game_with,
+    denormalized::DenormalizedState,
+    godot_shuffle::Seed,
+    parser::read_from_with,
+    report::OutcomeKind,
+    solver::{Outcome, SolveResult},
+    state::State,
+};
+
+/// A range of seeds, written `START..END` without `END` or `START..=END` with it, each bound
+/// written like a [`Seed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedRange(RangeInclusive<i64>);
+
+impl FromStr for SeedRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end, inclusive) = match s.split_once("..=") {
+            Some((start, end)) => (start, end, true),
+            None => {
+                let (start, end) = s
+                    .split_once("..")
+                    .ok_or_else(|| anyhow!("expected START..END or START..=END: {s}"))?;
+                (start, end, false)
+            }
+        };
+        let start = i64::from(start.parse::<Seed>()?);
+        let end = i64::from(end.parse::<Seed>()?);
+        let end = if inclusive {
+            end
+        } else {
+            end.checked_sub(1)
+                .ok_or_else(|| anyhow!("empty seed range: {s}"))?
+        };
+        Ok(SeedRange(start..=end))
+    }
+}
+
+impl SeedRange {
+    pub fn seeds(&self) -> impl Iterator<Item = Seed> + use<> {
+        self.0.clone().map(Seed::from)
+    }
+
+    /// The seeds of the range, split between the threads of the global [`rayon`] thread pool.
+    pub fn par_seeds(&self) -> impl ParallelIterator<Item = Seed> + use<> {
+        self.0.clone().into_par_iter().map(Seed::from)
+    }
+
+    /// Number of seeds of the range.
+    pub fn seed_count(&self) -> u128 {
+        (i128::from(*self.0.end()) - i128::from(*self.0.start()) + 1).max(0) as u128
+    }
+
+    /// Consecutive ranges of `size` seeds covering the range, the last one possibly shorter.
+    pub fn chunks(&self, size: u64) -> impl Iterator<Item = SeedRange> + use<> {
+        let (mut next, end) = (Some(*self.0.start()), *self.0.end());
+        std::iter::from_fn(move || {
+            let start = next.filter(|start| *start <= end)?;
+            let last = start.saturating_add_unsigned(size - 1).min(end);
+            next = last.checked_add(1);
+            Some(SeedRange(start..=last))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum DealSource {
+    Seed(Seed),
+    Cards(DenormalizedState),
+}
+
+/// A deal to solve, named after its seed or the file it was read from.
+#[derive(Debug, Clone)]
+pub struct Deal {
+    pub name: String,
+    source: DealSource,
+}
+
+impl From<Seed> for Deal {
+    fn from(seed: Seed) -> Self {
+        Self {
+            name: seed.to_string(),
+            source: DealSource::Seed(seed),
+        }
+    }
+}
+
+impl Deal {
+    /// The initial state, dealt from the seed for the variant described by `config` if needed.
+    pub fn state(&self, config: &GameConfig) -> DenormalizedState {
+        match &self.source {
+            DealSource::Seed(seed) => generate_game_with(*seed, config),
+            DealSource::Cards(state) => state.clone(),
+        }
+    }
+}
+
+/// Reads a list of deals of the variant described by `config`, one per line: either a seed or the
+/// path of a file in the format of [`read_from_with`], relative to `base`. Blank lines are
+/// ignored and `//` starts a comment.
+///
+/// Every file is read and validated right away, so that a broken list fails before solving.
+pub fn read_deals(
+    read: impl BufRead,
+    base: &Path,
+    config: &GameConfig,
+) -> anyhow::Result<Vec<Deal>> {
+    let mut deals = vec![];
+    for (line_number, line) in read.lines().enumerate() {
+        let line = line?;
+        let entry = match line.find("//") {
+            Some(comment_start) => &line[..comment_start],
+            None => &line,
+        }
+        .trim();
+        if entry.is_empty() {
+            continue;
+        }
+
+        let deal = match entry.parse::<Seed>() {
+            Ok(seed) => Deal::from(seed),
+            Err(_) => {
+                let path = base.join(entry);
+                let state = File::open(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|file| Ok(read_from_with(BufReader::new(file), config)?))
+                    .with_context(|| {
+                        format!("line {}: reading {}", line_number + 1, path.display())
+                    })?;
+                state.normalize().is_valid_with(config).with_context(|| {
+                    format!("line {}: validating {}", line_number + 1, path.display())
+                })?;
+                Deal {
+                    name: entry.to_string(),
+                    source: DealSource::Cards(state),
+                }
+            }
+        };
+        deals.push(deal);
+    }
+    Ok(deals)
+}
+
+/// The result of solving one deal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// The seed or file name of the deal.
+    pub deal: String,
+    pub outcome: OutcomeKind,
+    /// Unknown when the budget ran out.
+    pub solvable: Option<bool>,
+    /// Number of moves of the solution.
+    pub length: Option<usize>,
+    pub expanded_nodes: usize,
+    pub time_seconds: f64,
+}
+
+impl Record {
+    pub fn new(deal: String, result: &SolveResult) -> Self {
+        let (outcome, solvable) = match result.outcome {
+            Outcome::Solved(_) => (OutcomeKind::Solved, Some(true)),
+            Outcome::Unsolvable { .. } => (OutcomeKind::Unsolvable, Some(false)),
+            Outcome::BudgetExceeded { .. } => (OutcomeKind::BudgetExceeded, None),
+        };
+        Self {
+            deal,
+            outcome,
+            solvable,
+            length: result.solution().map(|solution| solution.moves.len()),
+            expanded_nodes: result.statistics.expanded_nodes,
+            time_seconds: result.statistics.elapsed.as_secs_f64(),
+        }
+    }
+}
+
+/// Format of a results file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Comma separated values, with a header line.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl RecordFormat {
+    /// JSON Lines for `.jsonl` and `.json` files, CSV otherwise.
+    pub fn from_extension(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl" | "json") => RecordFormat::JsonLines,
+            _ => RecordFormat::Csv,
+        }
+    }
+
+    fn parse(&self, line: &str) -> anyhow::Result<Record> {
+        Ok(match self {
+            RecordFormat::Csv => csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(line.as_bytes())
+                .deserialize()
+                .next()
+                .ok_or_else(|| anyhow!("empty record"))??,
+            RecordFormat::JsonLines => serde_json::from_str(line)?,
+        })
+    }
+
+    fn write(&self, record: &Record, write: &mut impl Write) -> anyhow::Result<()> {
+        match self {
+            RecordFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(write);
+                writer.serialize(record)?;
+                writer.flush()?;
+            }
+            RecordFormat::JsonLines => {
+                serde_json::to_writer(&mut *write, record)?;
+                writeln!(write)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+const CSV_HEADER: &str = "deal,outcome,solvable,length,expanded_nodes,time_seconds";
+
+/// Appends records to a results file, one complete line at a time, so that an interrupted batch
+/// can be resumed with [`RecordWriter::resume`].
+pub struct RecordWriter {
+    format: RecordFormat,
+    file: BufWriter<File>,
+}
+
+impl RecordWriter {
+    /// Opens the results file at `path`, creating it if needed, and returns the names of the deals
+    /// it already has a record for. A last line left incomplete by an interruption is dropped.
+    pub fn resume(path: &Path, format: RecordFormat) -> anyhow::Result<(Self, HashSet<String>)> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+
+        let complete = content.rfind('\n').map_or(0, |end| end + 1);
+        if complete < content.len() {
+            file.set_len(complete as u64)?;
+            file.seek(std::io::SeekFrom::End(0))?;
+        }
+
+        let mut done = HashSet::new();
+        for (line_number, line) in content[..complete].lines().enumerate() {
+            if line_number == 0 && format == RecordFormat::Csv && line == CSV_HEADER {
+                continue;
+            }
+            let record = format
+                .parse(line)
+                .with_context(|| format!("{}, line {}", path.display(), line_number + 1))?;
+            done.insert(record.deal);
+        }
+
+        let mut file = BufWriter::new(file);
+        if complete == 0 && format == RecordFormat::Csv {
+            writeln!(file, "{CSV_HEADER}")?;
+            file.flush()?;
+        }
+        Ok((Self { format, file }, done))
+    }
+
+    /// Writes `record` as a complete line and flushes it.
+    pub fn write(&mut self, record: &Record) -> anyhow::Result<()> {
+        self.format.write(record, &mut self.file)?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Solves every deal, dealt for the variant described by `config`, with `solve` on the global
+/// [`rayon`] thread pool, handing each record to `on_record` as soon as it is ready, in no
+/// particular order. Stops at the first error of `on_record`.
+pub fn solve_deals(
+    deals: &[Deal],
+    config: &GameConfig,
+    solve: impl Fn(&State) -> SolveResult + Sync,
+    on_record: impl Fn(Record) -> anyhow::Result<()> + Sync,
+) -> anyhow::Result<()> {
+    deals.par_iter().try_for_each(|deal| {
+        let result = solve(&deal.state(config).normalize());
+        on_record(Record::new(deal.name.clone(), &result))
+    })
+}
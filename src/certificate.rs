@@ -0,0 +1,91 @@
+//! Independently checkable proofs that a deal cannot be won.
+
+use std::io::{BufRead, Write};
+
+use rustc_hash::FxHashSet;
+use thiserror::Error;
+
+use crate::state::State;
+
+/// Every state reachable from `start`, none of which is won.
+///
+/// The set is closed under moves: every successor of a listed state is listed too. Together with
+/// `start` being listed, this proves that no sequence of moves wins the game, which
+/// [`UnsolvabilityCertificate::check`] verifies without trusting the solver.
+#[derive(Debug, Clone)]
+pub struct UnsolvabilityCertificate {
+    pub start: State,
+    pub reachable: Vec<State>,
+}
+
+/// Reason for rejecting an [`UnsolvabilityCertificate`].
+#[derive(Error, Debug)]
+pub enum CertificateError {
+    #[error("the start state is not listed as reachable")]
+    MissingStart,
+    #[error("reachable state {index} is won")]
+    WinningState { index: usize },
+    #[error("a successor of reachable state {index} is not listed")]
+    NotClosed { index: usize },
+    #[error("line {line}: invalid state")]
+    Parse {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("the certificate is empty")]
+    Empty,
+    #[error("read or write error")]
+    Io(#[from] std::io::Error),
+}
+
+impl UnsolvabilityCertificate {
+    /// Checks that the listed states contain the start, include no win and are closed under
+    /// moves.
+    pub fn check(&self) -> Result<(), CertificateError> {
+        let reachable = FxHashSet::from_iter(&self.reachable);
+
+        if !reachable.contains(&self.start) {
+            return Err(CertificateError::MissingStart);
+        }
+
+        for (index, state) in self.reachable.iter().enumerate() {
+            if state.is_win() {
+                return Err(CertificateError::WinningState { index });
+            }
+            if !state
+                .get_next_states()
+                .all(|(_, successor)| reachable.contains(&successor))
+            {
+                return Err(CertificateError::NotClosed { index });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the certificate as JSON lines, the start state first and then every reachable
+    /// state, using the schema of the [`report`](crate::report) module.
+    pub fn write_to(&self, mut write: impl Write) -> Result<(), CertificateError> {
+        for state in [&self.start].into_iter().chain(&self.reachable) {
+            serde_json::to_writer(&mut write, state).map_err(std::io::Error::from)?;
+            writeln!(write)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a certificate written by [`UnsolvabilityCertificate::write_to`].
+    pub fn read_from(read: impl BufRead) -> Result<Self, CertificateError> {
+        let mut states = read.lines().enumerate().map(|(index, line)| {
+            serde_json::from_str(&line?).map_err(|source| CertificateError::Parse {
+                line: index + 1,
+                source,
+            })
+        });
+
+        let start = states.next().ok_or(CertificateError::Empty)??;
+        let reachable = states.collect::<Result<_, _>>()?;
+
+        Ok(Self { start, reachable })
+    }
+}
@@ -1,5 +1,13 @@
-use std::collections::{BTreeMap, btree_map::Entry};
+//! Collections used by the game model.
 
+use std::{
+    collections::{BTreeMap, btree_map},
+    iter::{FlatMap, RepeatN},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Ordered multiset, used to make states independent of stack and placeholder order.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct BTreeMultiSet<T> {
     map: BTreeMap<T, usize>,
@@ -33,26 +41,49 @@ where
     }
 }
 
+impl<T: Serialize> Serialize for BTreeMultiSet<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            self.map
+                .iter()
+                .flat_map(|(value, count)| std::iter::repeat_n(value, *count)),
+        )
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord + Clone> Deserialize<'de> for BTreeMultiSet<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<T>::deserialize(deserializer).map(Self::from_iter)
+    }
+}
+
+impl<T: Clone> IntoIterator for BTreeMultiSet<T> {
+    type Item = T;
+    type IntoIter =
+        FlatMap<btree_map::IntoIter<T, usize>, RepeatN<T>, fn((T, usize)) -> RepeatN<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map
+            .into_iter()
+            .flat_map(|(value, count)| std::iter::repeat_n(value, count))
+    }
+}
+
 impl<T> BTreeMultiSet<T>
 where
     T: Ord + Clone,
 {
-    pub fn iter(&self) -> impl Iterator<Item = &T> {
+    pub fn iter(&self) -> impl Iterator<Item = &T> + Clone {
         self.map
             .iter()
             .flat_map(|(value, count)| std::iter::repeat_n(value, *count))
     }
 
-    pub fn into_iter(self) -> impl Iterator<Item = T> {
-        self.map
-            .into_iter()
-            .flat_map(|(value, count)| std::iter::repeat_n(value, count))
-    }
-
+    /// Removes one occurrence of `value`, returning it if it was present.
     pub fn take(&mut self, value: &T) -> Option<T> {
         match self.map.entry(value.clone()) {
-            Entry::Vacant(_) => None,
-            Entry::Occupied(mut occupied_entry) => {
+            btree_map::Entry::Vacant(_) => None,
+            btree_map::Entry::Occupied(mut occupied_entry) => {
                 *occupied_entry.get_mut() -= 1;
                 if *occupied_entry.get() == 0 {
                     occupied_entry.remove();
@@ -68,10 +99,10 @@ where
 
     pub fn insert(&mut self, value: T) {
         match self.map.entry(value) {
-            Entry::Vacant(entry) => {
+            btree_map::Entry::Vacant(entry) => {
                 entry.insert(1);
             }
-            Entry::Occupied(mut occupied_entry) => {
+            btree_map::Entry::Occupied(mut occupied_entry) => {
                 *occupied_entry.get_mut() += 1;
             }
         }
@@ -0,0 +1,156 @@
+//! Variants of the game: how many columns and slots the table has and which cards the deck holds.
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    packed,
+    state::{Beast, Card, CardColor, CardNumber, NormalCard},
+};
+
+/// Every color a deck can use, in dealing order.
+pub const COLORS: [CardColor; 4] = [
+    CardColor::Teal,
+    CardColor::Red,
+    CardColor::Blue,
+    CardColor::Green,
+];
+
+/// Every beast a deck can hold, in dealing order.
+pub const BEASTS: [Beast; 3] = [Beast::Tentacle, Beast::Whale, Beast::Ship];
+
+/// Highest rank of a numbered card, written with a single digit.
+pub const MAX_RANKS: u8 = 9;
+
+/// Most pirates a deck can hold, two for each beast.
+pub const MAX_PIRATES: usize = 2 * BEASTS.len();
+
+/// Most cards a deck can hold.
+pub const MAX_CARDS: usize = MAX_RANKS as usize * COLORS.len() + MAX_PIRATES + BEASTS.len();
+
+/// Most columns a table can have.
+pub const MAX_STACKS: usize = 15;
+
+/// Most slots a table can have.
+pub const MAX_SLOTS: usize = 7;
+
+/// Shape of a game. Every beast is bundled with two pirates, so the deck holds one beast for
+/// every two pirates, taken in the order of [`BEASTS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GameConfig {
+    /// Number of columns the deck is dealt on.
+    pub stacks: usize,
+    /// Number of slots, each holding a card or a beast bundle.
+    pub slots: usize,
+    /// Highest number of the numbered cards, which run from 1.
+    pub ranks: u8,
+    /// Number of colors of the numbered cards, the first ones of [`COLORS`].
+    pub colors: usize,
+    /// Number of pirates.
+    pub pirates: usize,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// A [`GameConfig`] the tools cannot handle.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    #[error("the number of {what} must be between {min} and {max}, not {found}")]
+    OutOfRange {
+        what: &'static str,
+        min: usize,
+        max: usize,
+        found: usize,
+    },
+    #[error("the number of pirates must be even, two for each beast, not {0}")]
+    OddPirates(usize),
+    #[error(
+        "{cards} cards on {stacks} columns with {slots} slots are too many to be searched, use \
+         fewer cards, columns or slots"
+    )]
+    TooLarge {
+        cards: usize,
+        stacks: usize,
+        slots: usize,
+    },
+}
+
+impl GameConfig {
+    /// The game as published: 6 columns, 3 slots, cards 1 to 9 in teal, red and blue, 6 pirates
+    /// and 3 beasts.
+    pub const STANDARD: Self = Self {
+        stacks: 6,
+        slots: 3,
+        ranks: 9,
+        colors: 3,
+        pirates: 6,
+    };
+
+    /// Colors of the numbered cards, in dealing order.
+    pub fn card_colors(&self) -> impl Iterator<Item = CardColor> + Clone + use<> {
+        COLORS.into_iter().take(self.colors)
+    }
+
+    /// Numbers of the numbered cards, increasing.
+    pub fn card_numbers(&self) -> impl Iterator<Item = CardNumber> + Clone + use<> {
+        (1..=self.ranks).map(CardNumber)
+    }
+
+    /// Beasts of the deck, in dealing order.
+    pub fn beasts(&self) -> impl Iterator<Item = Beast> + use<> {
+        BEASTS.into_iter().take(self.pirates / 2)
+    }
+
+    /// Every card of the deck, in the order the game shuffles them from.
+    pub fn deck(&self) -> Vec<Card> {
+        self.card_numbers()
+            .cartesian_product(self.card_colors())
+            .map(|(number, color)| Card::Normal(NormalCard { number, color }))
+            .chain(std::iter::repeat_n(Card::Pirate, self.pirates))
+            .chain(self.beasts().map(Card::Beast))
+            .collect()
+    }
+
+    /// Number of cards of the deck.
+    pub fn card_count(&self) -> usize {
+        self.ranks as usize * self.colors + self.pirates + self.pirates / 2
+    }
+
+    /// Checks that the deck, the table and every state reachable from them can be dealt, read,
+    /// printed and searched.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let ranges = [
+            ("columns", 1, MAX_STACKS, self.stacks),
+            ("slots", 0, MAX_SLOTS, self.slots),
+            ("ranks", 1, MAX_RANKS as usize, self.ranks as usize),
+            ("colors", 1, COLORS.len(), self.colors),
+            ("pirates", 0, MAX_PIRATES, self.pirates),
+        ];
+        for (what, min, max, found) in ranges {
+            if !(min..=max).contains(&found) {
+                return Err(ConfigError::OutOfRange {
+                    what,
+                    min,
+                    max,
+                    found,
+                });
+            }
+        }
+        if !self.pirates.is_multiple_of(2) {
+            return Err(ConfigError::OddPirates(self.pirates));
+        }
+        if !packed::fits::<{ packed::WIDE_WORDS }>(self) {
+            return Err(ConfigError::TooLarge {
+                cards: self.card_count(),
+                stacks: self.stacks,
+                slots: self.slots,
+            });
+        }
+        Ok(())
+    }
+}
@@ -1,37 +1,17 @@
-use std::{array, collections::VecDeque};
+//! Deck creation and dealing, reproducing the game.
 
-use itertools::Itertools;
+use std::collections::VecDeque;
 
 use crate::{
+    config::GameConfig,
+    denormalized::{DenormalizedState, PlaceHolders},
     godot_shuffle::{self, Seed},
-    denormalized::DenormalizedState,
-    state::{Beast, Card, CardColor, CardNumber, CardStack, NormalCard},
+    state::{Card, CardStack},
 };
 
-fn new_deck() -> Vec<Card> {
-    let mut deck: Vec<Card> = vec![];
-
-    deck.extend(
-        (1..=9)
-            .cartesian_product([CardColor::Teal, CardColor::Red, CardColor::Blue])
-            .map(|(number, color)| {
-                Card::Normal(NormalCard {
-                    number: CardNumber(number),
-                    color,
-                })
-            }),
-    );
-
-    deck.extend(std::iter::repeat_n(Card::Pirate, 6));
-
-    deck.extend([Beast::Tentacle, Beast::Whale, Beast::Ship].map(Card::Beast));
-
-    deck
-}
-
-fn distribute(deck: Vec<Card>) -> DenormalizedState {
+fn distribute(deck: Vec<Card>, config: &GameConfig) -> DenormalizedState {
     let mut deck = VecDeque::from(deck);
-    let mut stacks: [CardStack; 6] = array::repeat(CardStack { cards: vec![] });
+    let mut stacks = vec![CardStack { cards: vec![] }; config.stacks];
     let mut stack_index_iterator = (0..stacks.len()).cycle();
 
     while let Some(card) = deck.pop_front() {
@@ -40,14 +20,20 @@ fn distribute(deck: Vec<Card>) -> DenormalizedState {
     }
 
     DenormalizedState {
-        board: stacks.into(),
+        board: stacks,
         output: Default::default(),
-        placeholders: Default::default(),
+        placeholders: PlaceHolders::empty(config.slots),
     }
 }
 
+/// Deals the same layout the game produces for `seed`.
 pub fn generate_game(seed: Seed) -> DenormalizedState {
-    let mut deck = new_deck();
+    generate_game_with(seed, &GameConfig::STANDARD)
+}
+
+/// Deals the variant described by `config`, shuffling its deck as the game does for `seed`.
+pub fn generate_game_with(seed: Seed, config: &GameConfig) -> DenormalizedState {
+    let mut deck = config.deck();
     godot_shuffle::shuffle(&mut deck, seed);
-    distribute(deck)
+    distribute(deck, config)
 }
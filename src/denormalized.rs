@@ -1,77 +1,193 @@
-use crate::state::{self, CardStack, Output, PlaceHolder, State};
+//! On-screen representation of a game state.
 
-pub struct DenormalizationInformation {
-    initial_stacks: Vec<CardStack>,
-}
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{CardSource, Move},
+    config::GameConfig,
+    rules,
+    solver::Solution,
+    state::{self, Card, CardOrBundle, CardStack, Layout, Output, PlaceHolder, State},
+};
 
-#[derive(Debug, Clone, PartialOrd, Ord, Hash, PartialEq, Eq)]
+/// Placeholders in their on-screen order.
+#[derive(Debug, Clone, PartialOrd, Ord, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PlaceHolders {
     pub holes: Vec<PlaceHolder>,
 }
 
-impl Default for PlaceHolders {
-    fn default() -> Self {
+impl PlaceHolders {
+    /// `count` empty placeholders.
+    pub fn empty(count: usize) -> Self {
         Self {
-            holes: Vec::from_iter([PlaceHolder::default(); 3]),
+            holes: vec![PlaceHolder::default(); count],
         }
     }
 }
 
+impl Default for PlaceHolders {
+    fn default() -> Self {
+        Self::empty(GameConfig::STANDARD.slots)
+    }
+}
+
+/// Game state as displayed by the game, with stacks and placeholders in their on-screen order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DenormalizedState {
     pub placeholders: PlaceHolders,
     pub output: Output,
     pub board: Vec<CardStack>,
 }
 
-impl State {
-    pub fn denormalize(
-        self,
-        denormalization_information: &DenormalizationInformation,
-    ) -> DenormalizedState {
-        let mut board = vec![];
-        let mut available_stacks = self.board;
-        for initial_stack in &denormalization_information.initial_stacks {
-            let matching_stack = available_stacks
-                .iter()
-                .max_by_key(|stack| {
-                    initial_stack
-                        .cards
-                        .iter()
-                        .zip(stack.cards.iter())
-                        .take_while(|(initial, current)| initial == current)
-                        .count()
-                })
-                .expect("should never be empty")
-                .clone();
-
-            let matching_stack = available_stacks.remove(&matching_stack).unwrap();
-            board.push(matching_stack);
-        }
+/// A [`Solution`] replayed on screen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DenormalizedSolution {
+    /// Every on-screen state from the start to the won one, both included.
+    pub states: Vec<DenormalizedState>,
+    /// The on-screen move leading from each state to the next one.
+    pub moves: Vec<Move>,
+}
 
-        DenormalizedState {
-            placeholders: PlaceHolders {
-                holes: self.placeholders.holes.into_iter().collect(),
-            },
-            output: self.output,
-            board,
-        }
+impl Layout for DenormalizedState {
+    fn stacks(&self) -> impl Iterator<Item = &CardStack> {
+        self.board.iter()
+    }
+
+    fn holes(&self) -> impl Iterator<Item = &PlaceHolder> {
+        self.placeholders.holes.iter()
+    }
+
+    fn output(&self) -> &Output {
+        &self.output
     }
 }
 
 impl DenormalizedState {
-    pub fn normalize(self) -> (State, DenormalizationInformation) {
-        let information = DenormalizationInformation {
-            initial_stacks: self.board.clone(),
-        };
+    /// Every legal move, referring to the on-screen columns and slots.
+    pub fn legal_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        rules::legal_moves(self)
+    }
 
-        let state = State {
-            board: self.board.into_iter().collect(),
+    /// Converts into the [`State`] used by the solver.
+    pub fn normalize(&self) -> State {
+        State {
+            board: self.board.iter().cloned().collect(),
             output: self.output,
             placeholders: state::PlaceHolders {
-                holes: self.placeholders.holes.into_iter().collect(),
+                holes: self.placeholders.holes.iter().copied().collect(),
             },
-        };
+        }
+    }
+
+    fn pop(&mut self, source: &CardSource) -> Card {
+        match *source {
+            CardSource::Stack(column) => self.board[column]
+                .cards
+                .pop()
+                .expect("stack should not be empty"),
+            CardSource::PlaceHolder(slot) => match self.placeholders.holes[slot].0.take() {
+                Some(CardOrBundle::Card(card)) => card,
+                _ => panic!("slot should hold a card"),
+            },
+        }
+    }
+
+    fn fill(&mut self, slot: usize, content: CardOrBundle) {
+        let hole = &mut self.placeholders.holes[slot];
+        assert!(hole.0.is_none(), "placeholder should be empty");
+        hole.0 = Some(content);
+    }
+
+    /// Plays `mv`, whose indices refer to the columns and slots of `self` as returned by
+    /// [`DenormalizedState::translate`].
+    pub fn apply(&mut self, mv: &Move) {
+        match *mv {
+            Move::Output { from } => {
+                let Card::Normal(card) = self.pop(&from) else {
+                    panic!("only numbered cards can be sent to the output");
+                };
+                self.output[&card.color].next_inplace();
+            }
+            Move::StackToStack { from, to, count } => {
+                let from = &mut self.board[from].cards;
+                let cards = from.split_off(from.len() - count);
+                self.board[to].cards.extend(cards);
+            }
+            Move::StackToPlaceHolder { from, to } => {
+                let card = self.pop(&CardSource::Stack(from));
+                self.fill(to, CardOrBundle::Card(card));
+            }
+            Move::PlaceHolderToStack { from, to } => {
+                let card = self.pop(&CardSource::PlaceHolder(from));
+                self.board[to].cards.push(card);
+            }
+            Move::PromoteBeast {
+                beast,
+                pirates,
+                into,
+            } => {
+                let Card::Beast(beast) = self.pop(&beast) else {
+                    panic!("should be a beast");
+                };
+                for pirate in &pirates {
+                    assert_eq!(self.pop(pirate), Card::Pirate);
+                }
+                self.fill(into, CardOrBundle::BeastBundle(beast));
+            }
+        }
+    }
 
-        (state, information)
+    /// Replays a solution found for the normalized form of `self`, keeping every column and
+    /// slot in its on-screen position.
+    pub fn replay(&self, solution: &Solution) -> DenormalizedSolution {
+        let mut states = vec![self.clone()];
+        let mut moves = vec![];
+        for (state, mv) in solution.states.iter().zip(&solution.moves) {
+            let mut on_screen = states.last().unwrap().clone();
+            let mv = on_screen.translate(state, mv);
+            on_screen.apply(&mv);
+            moves.push(mv);
+            states.push(on_screen);
+        }
+
+        DenormalizedSolution { states, moves }
+    }
+}
+
+/// Finds the on-screen position of the `index`-th normalized element, telling equal elements
+/// apart by their order of appearance.
+fn screen_index<'a, T: Eq + 'a>(
+    normalized: impl Iterator<Item = &'a T>,
+    screen: &[T],
+    index: usize,
+) -> usize {
+    let normalized = normalized.collect_vec();
+    let target = normalized[index];
+    let nth_equal = normalized[..index]
+        .iter()
+        .filter(|value| **value == target)
+        .count();
+    screen
+        .iter()
+        .positions(|value| value == target)
+        .nth(nth_equal)
+        .expect("normalized and on-screen states should hold the same elements")
+}
+
+impl DenormalizedState {
+    /// Translates `mv`, generated from the normalized `state`, to the on-screen columns and
+    /// slots of `self`, which must hold the same stacks and placeholders.
+    pub fn translate(&self, state: &State, mv: &Move) -> Move {
+        mv.map_indices(
+            |index| screen_index(state.board.iter(), &self.board, index),
+            |index| {
+                screen_index(
+                    state.placeholders.holes.iter(),
+                    &self.placeholders.holes,
+                    index,
+                )
+            },
+        )
     }
 }
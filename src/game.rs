@@ -0,0 +1,172 @@
+//! A game played by hand, one on-screen move at a time, with unlimited undo and redo.
+
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::{
+    actions::{CardSource, Move},
+    denormalized::DenormalizedState,
+    rules,
+};
+
+/// A place of the table a card can be taken from or put on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spot {
+    Column(usize),
+    Slot(usize),
+    Output,
+}
+
+impl Display for Spot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Spot::Column(column) => write!(f, "column {}", column + 1),
+            Spot::Slot(slot) => write!(f, "slot {}", slot + 1),
+            Spot::Output => f.write_str("the output"),
+        }
+    }
+}
+
+impl From<CardSource> for Spot {
+    fn from(source: CardSource) -> Self {
+        match source {
+            CardSource::Stack(column) => Spot::Column(column),
+            CardSource::PlaceHolder(slot) => Spot::Slot(slot),
+        }
+    }
+}
+
+impl Move {
+    /// Where the card, or the beast of a promotion, is taken from.
+    fn origin(&self) -> Spot {
+        match *self {
+            Move::Output { from } => from.into(),
+            Move::StackToStack { from, .. } | Move::StackToPlaceHolder { from, .. } => {
+                Spot::Column(from)
+            }
+            Move::PlaceHolderToStack { from, .. } => Spot::Slot(from),
+            Move::PromoteBeast { beast, .. } => beast.into(),
+        }
+    }
+
+    /// Where the card, or the bundle of a promotion, ends up.
+    fn destination(&self) -> Spot {
+        match *self {
+            Move::Output { .. } => Spot::Output,
+            Move::StackToStack { to, .. } | Move::PlaceHolderToStack { to, .. } => Spot::Column(to),
+            Move::StackToPlaceHolder { to, .. } => Spot::Slot(to),
+            Move::PromoteBeast { into, .. } => Spot::Slot(into),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameError {
+    #[error("illegal move")]
+    IllegalMove,
+    #[error("nothing to undo")]
+    NothingToUndo,
+    #[error("nothing to redo")]
+    NothingToRedo,
+}
+
+/// A game in progress: the current on-screen state and the moves played to reach it.
+#[derive(Debug, Clone)]
+pub struct Game {
+    state: DenormalizedState,
+    /// Every move played, with the state it was played from.
+    past: Vec<(DenormalizedState, Move)>,
+    /// Moves taken back, the latest last.
+    future: Vec<Move>,
+}
+
+impl Game {
+    pub fn new(state: DenormalizedState) -> Self {
+        Self {
+            state,
+            past: vec![],
+            future: vec![],
+        }
+    }
+
+    pub fn state(&self) -> &DenormalizedState {
+        &self.state
+    }
+
+    /// Number of moves played, not counting the ones taken back.
+    pub fn moves_played(&self) -> usize {
+        self.past.len()
+    }
+
+    /// Whether the game is won, see [`rules::is_win`].
+    pub fn is_won(&self) -> bool {
+        rules::is_win(&self.state)
+    }
+
+    /// Plays `mv`, referring to the on-screen columns and slots, if it is legal. Forgets the
+    /// moves taken back.
+    pub fn play(&mut self, mv: Move) -> Result<(), GameError> {
+        if !self.state.legal_moves().any(|legal| legal == mv) {
+            return Err(GameError::IllegalMove);
+        }
+        self.future.clear();
+        self.push(mv);
+        Ok(())
+    }
+
+    fn push(&mut self, mv: Move) {
+        let previous = self.state.clone();
+        self.state.apply(&mv);
+        self.past.push((previous, mv));
+    }
+
+    /// Takes back the latest move played.
+    pub fn undo(&mut self) -> Result<Move, GameError> {
+        let (previous, mv) = self.past.pop().ok_or(GameError::NothingToUndo)?;
+        self.state = previous;
+        self.future.push(mv);
+        Ok(mv)
+    }
+
+    /// Plays again the latest move taken back.
+    pub fn redo(&mut self) -> Result<Move, GameError> {
+        let mv = self.future.pop().ok_or(GameError::NothingToRedo)?;
+        self.push(mv);
+        Ok(mv)
+    }
+
+    /// Numbers of cards that can be moved together from the top of `column` onto another column,
+    /// in increasing order.
+    pub fn movable_counts(&self, column: usize) -> Vec<usize> {
+        let mut counts = self
+            .state
+            .legal_moves()
+            .filter_map(|mv| match mv {
+                Move::StackToStack { from, count, .. } if from == column => Some(count),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        counts.sort_unstable();
+        counts.dedup();
+        counts
+    }
+
+    /// The legal move taking the top card at `from` to `to`, or the top `count` cards when moving
+    /// between columns, the most that can go there by default. Moving a beast to a slot promotes
+    /// it when possible.
+    pub fn find_move(&self, from: Spot, to: Spot, count: Option<usize>) -> Option<Move> {
+        self.state
+            .legal_moves()
+            .filter(|mv| mv.origin() == from && mv.destination() == to)
+            .filter(|mv| match (mv, count) {
+                (Move::StackToStack { count, .. }, Some(wanted)) => *count == wanted,
+                _ => true,
+            })
+            .max_by_key(|mv| match mv {
+                Move::PromoteBeast { .. } => usize::MAX,
+                Move::StackToStack { count, .. } => *count,
+                _ => 0,
+            })
+    }
+}
@@ -1,32 +1,96 @@
-use std::str::FromStr;
+//! Port of the Godot shuffling algorithm.
+
+use std::{fmt::Display, str::FromStr};
 
 use anyhow::Context;
 use rand::RngCore;
 use rand_pcg::Pcg32;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
 
+/// Game seed, written as in the game with a `_` prefix for negative values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Seed(i64);
 
+impl From<i64> for Seed {
+    fn from(value: i64) -> Self {
+        Seed(value)
+    }
+}
+
+impl From<Seed> for i64 {
+    fn from(seed: Seed) -> Self {
+        seed.0
+    }
+}
+
+impl Display for Seed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0 < 0 {
+            write!(f, "_{}", self.0.unsigned_abs())
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
 impl FromStr for Seed {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (num, mul) = match s.strip_prefix('_') {
-            Some(neg_num) => (neg_num, -1),
-            None => (s, 1),
+        let (num, negative) = match s.strip_prefix('_') {
+            Some(neg_num) => (neg_num, true),
+            None => (s, false),
         };
-        let seed = num
+        let magnitude = num
             .parse::<u64>()
-            .with_context(|| format!("parsing value: {num}"))? as i64
-            * mul;
-        Ok(Seed(seed))
+            .with_context(|| format!("parsing value: {num}"))?;
+        let seed = if negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        };
+        seed.map(Seed)
+            .with_context(|| format!("seed out of the 64-bit range: {s}"))
     }
 }
 
+impl Serialize for Seed {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Seed {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Shuffles `deck` exactly like Godot's `Array.shuffle` with the given seed.
 pub fn shuffle<T>(deck: &mut [T], seed: Seed) {
+    shuffle_while(deck, seed, |_, _| true);
+}
+
+/// Whether shuffling `deck` like [`shuffle`] with `seed` gives `target`. `deck` is left partly
+/// shuffled, as the shuffle stops at the first element settling somewhere else than in `target`.
+pub fn shuffles_into<T: PartialEq>(deck: &mut [T], seed: Seed, target: &[T]) -> bool {
+    deck.len() == target.len() && shuffle_while(deck, seed, |index, card| *card == target[index])
+}
+
+/// Shuffles `deck` like [`shuffle`], handing every index to `settled` with its final element as
+/// soon as it is known, from the last one down, and stopping as soon as `settled` returns false.
+/// Returns whether the shuffle ran to the end.
+fn shuffle_while<T>(
+    deck: &mut [T],
+    seed: Seed,
+    mut settled: impl FnMut(usize, &T) -> bool,
+) -> bool {
     let seed: u64 = i64::cast_unsigned(seed.0);
     let mut pcg = Pcg32::new(seed, 1442695040888963407);
 
     if deck.len() <= 1 {
-        return;
+        return deck.first().is_none_or(|card| settled(0, card));
     }
 
     let mut i = deck.len() - 1;
@@ -34,7 +98,11 @@ pub fn shuffle<T>(deck: &mut [T], seed: Seed) {
         let r = pcg.next_u32();
         let j = r as usize % (i + 1);
         deck.swap(i, j);
+        if !settled(i, &deck[i]) {
+            return false;
+        }
 
         i -= 1;
     }
+    settled(0, &deck[0])
 }
@@ -0,0 +1,33 @@
+//! Solver for the [Pirate Solitaire](https://github.com/Pheonyxior/Pirate-Solitaire-Git-Repo)
+//! card game.
+//!
+//! The game model lives in [`state`], legal moves are defined by [`rules`] and
+//! [`solver::solve`] searches for a winning line. Deals can be generated from a game seed with
+//! [`deck_generator::generate_game`] or read from text with [`parser::read_from`], and printed
+//! with the [`std::fmt::Display`] implementation in [`printer`].
+
+pub mod actions;
+mod ansi;
+pub mod batch;
+pub mod certificate;
+pub mod collection;
+pub mod config;
+pub mod deck_generator;
+pub mod denormalized;
+pub mod game;
+pub mod godot_shuffle;
+pub mod packed;
+pub mod parser;
+pub mod play;
+mod portfolio;
+pub mod position;
+pub mod printer;
+pub mod report;
+pub mod rules;
+mod search;
+pub mod seed_search;
+pub mod solver;
+pub mod state;
+pub mod validators;
+pub mod verify;
+mod zobrist;
@@ -1,86 +1,784 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use std::{
     fs::File,
-    io::{BufRead, BufReader},
-    path::PathBuf,
+    io::{BufReader, BufWriter, Write},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
-use anyhow::{Context, anyhow};
+use anyhow::Context;
 
-use crate::{
-    deck_generator::generate_game,
-    denormalized::DenormalizedState,
-    solver::solve,
-    state::{CardStack, Output},
+use solitaire_solver::{
+    batch::{Deal, RecordFormat, RecordWriter, SeedRange, read_deals, solve_deals},
+    certificate::UnsolvabilityCertificate,
+    config::GameConfig,
+    deck_generator::generate_game_with,
+    denormalized::{DenormalizedSolution, DenormalizedState},
+    godot_shuffle::Seed,
+    parser::read_from_with,
+    play,
+    report::SolveReport,
+    seed_search::{SeedFilter, SeedMatch, find_seeds, reverse_seed},
+    solver::{
+        AutoPlay, Budget, Heuristic, Limit, Outcome, Parallelism, SearchStrategy, SolverOptions,
+        explore, solve_with,
+    },
+    verify::verify,
 };
 
-mod actions;
-mod ansi;
-mod collection;
-mod deck_generator;
-mod denormalized;
-mod godot_shuffle;
-mod parser;
-mod printer;
-mod solver;
-mod state;
-mod validators;
-
-fn read_from(read: impl BufRead) -> anyhow::Result<DenormalizedState> {
-    let initial_stacks: [CardStack; 6] = read
-        .lines()
-        .map(|line| {
-            line.map_err(|read_error| anyhow!("line read error: {read_error}"))?
-                .parse()
-                .context("parsing card stack")
-        })
-        .take(6)
-        .collect::<Result<Vec<_>, _>>()
-        .context("parsing stacks")?
-        .try_into()
-        .map_err(|_| anyhow!("not enough card stacks"))?;
-
-    let state = DenormalizedState {
-        placeholders: Default::default(),
-        output: Output::default(),
-        board: initial_stacks.into(),
-    };
+/// Time limit of the searches run while playing, unless --time-limit is given.
+const PLAY_TIME_LIMIT: Duration = Duration::from_secs(10);
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+enum Format {
+    /// print the whole board after every step
+    #[default]
+    Board,
+    /// print one line per move
+    Moves,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+enum Output {
+    /// human readable text, see --format
+    #[default]
+    Text,
+    /// a single JSON document, see the `report` module documentation for its schema
+    Json,
+}
 
-    Ok(state)
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+enum Strategy {
+    /// A*, minimal solutions with --optimal
+    #[default]
+    Astar,
+    /// A* with a weighted heuristic, see --weight
+    WeightedAstar,
+    /// iterative deepening A*, using little memory
+    Idastar,
+    /// greedy best-first search
+    Greedy,
+    /// depth-first search, fast with long solutions
+    Dfs,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Records {
+    /// comma separated values with a header line
+    Csv,
+    /// one JSON object per line
+    Jsonl,
 }
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
-enum Args {
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        global = true,
+        help = "solution format"
+    )]
+    format: Format,
+    #[arg(long, value_enum, default_value_t, global = true, help = "output type")]
+    output: Output,
+    #[arg(
+        long,
+        global = true,
+        help = "search with an admissible heuristic, guaranteeing a solution with the minimum number of moves with the astar and idastar strategies, usually much slower"
+    )]
+    optimal: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        global = true,
+        help = "search algorithm"
+    )]
+    strategy: Strategy,
+    #[arg(
+        long,
+        default_value_t = 2.0,
+        global = true,
+        help = "heuristic weight of the weighted-astar strategy, at least 1"
+    )]
+    weight: f64,
+    #[arg(
+        long,
+        global = true,
+        help = "play outputs that can never hurt right away instead of searching around them"
+    )]
+    auto_play: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "race up to this many variations of the search on separate threads, the first to finish winning"
+    )]
+    parallel: Option<usize>,
+    #[arg(
+        long,
+        global = true,
+        requires = "parallel",
+        help = "with --parallel, give the same result on every run, barring time limits"
+    )]
+    deterministic: bool,
+    #[arg(long, global = true, help = "stop searching after this many seconds")]
+    time_limit: Option<f64>,
+    #[arg(
+        long,
+        global = true,
+        help = "stop searching after expanding this many states"
+    )]
+    node_limit: Option<usize>,
+    #[arg(
+        long,
+        global = true,
+        help = "stop searching once the stored states take about this many MiB"
+    )]
+    memory_limit: Option<usize>,
+    #[arg(
+        long,
+        global = true,
+        help = "explore every reachable state to prove unsolvable deals, ignoring --strategy"
+    )]
+    exhaustive: bool,
+    #[arg(
+        long,
+        global = true,
+        requires = "exhaustive",
+        help = "write the reachable states of an unsolvable deal to this file, see check-certificate"
+    )]
+    certificate: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = GameConfig::STANDARD.stacks,
+        help_heading = "Variant",
+        help = "number of columns the deck is dealt on"
+    )]
+    columns: usize,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = GameConfig::STANDARD.slots,
+        help_heading = "Variant",
+        help = "number of slots"
+    )]
+    slots: usize,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = GameConfig::STANDARD.ranks,
+        help_heading = "Variant",
+        help = "highest number of the numbered cards, up to 9"
+    )]
+    ranks: u8,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = GameConfig::STANDARD.colors,
+        help_heading = "Variant",
+        help = "number of colors of the numbered cards: teal, red, blue then green"
+    )]
+    colors: usize,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = GameConfig::STANDARD.pirates,
+        help_heading = "Variant",
+        help = "number of pirates, two for each beast: tentacle, whale then ship"
+    )]
+    pirates: usize,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
     #[command(name = "seed", about = "load scramble from seed")]
     Seed { seed: String },
     #[command(name = "cards", about = "load scramble from card disposition")]
     Cards { file: PathBuf },
+    #[command(
+        name = "check-certificate",
+        about = "check an unsolvability certificate written with --certificate"
+    )]
+    CheckCertificate { file: PathBuf },
+    #[command(
+        name = "hint",
+        about = "print only the next move from a card disposition and how many moves are left"
+    )]
+    Hint {
+        file: PathBuf,
+        #[arg(
+            long,
+            help = "then reveal the following moves one at a time, pressing Enter"
+        )]
+        reveal: bool,
+    },
+    #[command(
+        name = "verify",
+        about = "replay a solution written as by --format moves, reporting the first illegal move"
+    )]
+    Verify {
+        #[arg(help = "solution file, one move per line")]
+        moves: PathBuf,
+        #[arg(
+            long,
+            required_unless_present = "cards",
+            conflicts_with = "cards",
+            help = "seed of the deal"
+        )]
+        seed: Option<String>,
+        #[arg(long, help = "card disposition file of the starting position")]
+        cards: Option<PathBuf>,
+    },
+    #[command(
+        name = "play",
+        about = "play a deal in the terminal, with undo, redo and hints from the solver"
+    )]
+    Play {
+        #[arg(help = "seed to deal, a random one by default")]
+        seed: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "seed",
+            help = "play a card disposition file instead"
+        )]
+        cards: Option<PathBuf>,
+    },
+    #[command(
+        name = "batch",
+        about = "solve many deals in parallel, appending one record per deal to a results file"
+    )]
+    Batch {
+        #[arg(
+            long,
+            required_unless_present = "list",
+            conflicts_with = "list",
+            help = "seeds to solve, START..END or START..=END"
+        )]
+        seeds: Option<SeedRange>,
+        #[arg(
+            long,
+            help = "file with one seed or card disposition file per line, // starting a comment"
+        )]
+        list: Option<PathBuf>,
+        #[arg(help = "results file, deals already recorded in it are skipped")]
+        results: PathBuf,
+        #[arg(
+            long,
+            value_enum,
+            help = "results format, jsonl for .jsonl and .json files and csv otherwise by default"
+        )]
+        records: Option<Records>,
+        #[arg(long, help = "number of threads, one per core by default")]
+        threads: Option<usize>,
+    },
+    #[command(
+        name = "find-seeds",
+        about = "scan seeds in parallel, printing those whose deal meets every given criterion"
+    )]
+    FindSeeds {
+        #[arg(help = "seeds to scan, START..END or START..=END")]
+        seeds: SeedRange,
+        #[arg(
+            long,
+            help = "only deals won in at least this many moves at best, searched with --optimal and the astar or idastar strategy"
+        )]
+        min_length: Option<usize>,
+        #[arg(
+            long,
+            help = "only deals won in at most this many moves at best, searched with --optimal and the astar or idastar strategy"
+        )]
+        max_length: Option<usize>,
+        #[arg(
+            long,
+            conflicts_with_all = ["min_length", "max_length"],
+            help = "only deals proven impossible to win"
+        )]
+        unsolvable: bool,
+        #[arg(
+            long,
+            help = "only deals with at least this many cards on top of every beast"
+        )]
+        min_beast_depth: Option<usize>,
+        #[arg(
+            long,
+            help = "only deals with at most this many cards on top of every beast"
+        )]
+        max_beast_depth: Option<usize>,
+        #[arg(long, help = "only deals with every pirate in the same column")]
+        pirates_together: bool,
+        #[arg(long, help = "stop after finding this many seeds")]
+        count: Option<usize>,
+        #[arg(long, help = "number of threads, one per core by default")]
+        threads: Option<usize>,
+    },
+    #[command(
+        name = "reverse-seed",
+        about = "find the seed dealing a card disposition, scanning a range of seeds in parallel"
+    )]
+    ReverseSeed {
+        #[arg(help = "card disposition file of the deal, before any move")]
+        file: PathBuf,
+        #[arg(help = "seeds to scan, START..END or START..=END")]
+        seeds: SeedRange,
+        #[arg(long, help = "number of threads, one per core by default")]
+        threads: Option<usize>,
+    },
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let state = match args {
-        Args::Seed { seed } => generate_game(seed.parse()?),
-        Args::Cards { file } => read_from(BufReader::new(File::open(file)?))?,
+    let game = game_config(&args)?;
+    let mut options = solver_options(&args, game)?;
+    let state = match &args.command {
+        Command::Seed { seed } => generate_game_with(seed.parse()?, &game),
+        Command::Cards { file } | Command::Hint { file, .. } => {
+            read_from_with(BufReader::new(File::open(file)?), &game)?
+        }
+        Command::CheckCertificate { file } => {
+            let certificate =
+                UnsolvabilityCertificate::read_from(BufReader::new(File::open(file)?))
+                    .context("reading certificate")?;
+            certificate.check().context("invalid certificate")?;
+            println!(
+                "valid certificate: none of the {} states reachable from the start is won",
+                certificate.reachable.len()
+            );
+            return Ok(());
+        }
+        Command::Verify { moves, seed, cards } => {
+            let start = match (seed, cards) {
+                (Some(seed), _) => generate_game_with(seed.parse()?, &game),
+                (None, Some(file)) => read_from_with(BufReader::new(File::open(file)?), &game)?,
+                (None, None) => unreachable!("clap requires --seed or --cards"),
+            };
+            start
+                .normalize()
+                .is_valid_with(&game)
+                .context("validation error")?;
+            let replay = verify(&start, BufReader::new(File::open(moves)?))?;
+            anyhow::ensure!(replay.is_won(), "{replay}");
+            println!("valid solution: {replay}");
+            return Ok(());
+        }
+        Command::Play { seed, cards } => {
+            let (state, title) = match (seed, cards) {
+                (_, Some(file)) => (
+                    read_from_with(BufReader::new(File::open(file)?), &game)?,
+                    file.display().to_string(),
+                ),
+                (Some(seed), None) => (
+                    generate_game_with(seed.parse()?, &game),
+                    format!("seed {seed}"),
+                ),
+                (None, None) => {
+                    let seed = Seed::from(rand::random::<i64>());
+                    (generate_game_with(seed, &game), format!("seed {seed}"))
+                }
+            };
+            state
+                .normalize()
+                .is_valid_with(&game)
+                .context("validation error")?;
+            options.budget.time.get_or_insert(PLAY_TIME_LIMIT);
+            return play::run(state, title, options);
+        }
+        Command::Batch {
+            seeds,
+            list,
+            results,
+            records,
+            threads,
+        } => {
+            let deals = match (seeds, list) {
+                (Some(seeds), _) => seeds.seeds().map(Deal::from).collect(),
+                (None, Some(list)) => read_deals(
+                    BufReader::new(File::open(list)?),
+                    list.parent().unwrap_or(Path::new("")),
+                    &game,
+                )
+                .with_context(|| format!("reading {}", list.display()))?,
+                (None, None) => unreachable!("clap requires --seeds or --list"),
+            };
+            let format = match records {
+                Some(Records::Csv) => RecordFormat::Csv,
+                Some(Records::Jsonl) => RecordFormat::JsonLines,
+                None => RecordFormat::from_extension(results),
+            };
+            if let Some(threads) = threads {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(*threads)
+                    .build_global()?;
+            }
+            return batch(&args, &options, deals, results, format);
+        }
+        Command::FindSeeds {
+            seeds,
+            min_length,
+            max_length,
+            unsolvable,
+            min_beast_depth,
+            max_beast_depth,
+            pirates_together,
+            count,
+            threads,
+        } => {
+            let filter = SeedFilter {
+                min_length: *min_length,
+                max_length: *max_length,
+                unsolvable: *unsolvable,
+                min_beast_depth: *min_beast_depth,
+                max_beast_depth: *max_beast_depth,
+                pirates_together: *pirates_together,
+            };
+            if let Some(threads) = threads {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(*threads)
+                    .build_global()?;
+            }
+            return find_seeds_command(&args, &options, seeds, &filter, *count);
+        }
+        Command::ReverseSeed {
+            file,
+            seeds,
+            threads,
+        } => {
+            let state = read_from_with(BufReader::new(File::open(file)?), &game)?;
+            if let Some(threads) = threads {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(*threads)
+                    .build_global()?;
+            }
+            return reverse_seed_command(&state, seeds, &game);
+        }
     };
-    println!("{state}");
+    let normalized = state.normalize();
 
-    let (state, denormalization_information) = state.normalize();
+    normalized
+        .is_valid_with(&game)
+        .context("validation error")?;
 
-    state.is_valid().context("validation error")?;
+    let result = if args.exhaustive {
+        let exploration = explore(&normalized, &options.game, &options.budget);
+        if let (Some(path), Some(certificate)) = (&args.certificate, &exploration.certificate) {
+            certificate
+                .write_to(BufWriter::new(File::create(path)?))
+                .context("writing certificate")?;
+        }
+        exploration.result
+    } else {
+        solve_with(&normalized, &options)
+    };
+
+    if let Command::Hint { reveal, .. } = args.command {
+        // the exhaustive exploration returns the first win it reaches, however long
+        let minimal = options.finds_minimal_solutions() && !args.exhaustive;
+        return hint(&state, result.outcome, minimal, reveal);
+    }
 
-    if let Some(solution) = solve(&state) {
-        for (i, step) in solution.into_iter().enumerate() {
-            println!();
-            println!("==============");
-            println!();
-            println!("STEP {i}:");
-            println!("{}", step.denormalize(&denormalization_information));
+    if let Output::Json = args.output {
+        let report = SolveReport::new(state, &result);
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+
+    println!("{}", state.display_with(&game));
+
+    match result.outcome {
+        Outcome::Solved(solution) => print_line(&state.replay(&solution), args.format, &game),
+        Outcome::Unsolvable {
+            reachable_states: Some(reachable_states),
+        } => println!(
+            "no solution: none of the {reachable_states} states reachable from the start is won"
+        ),
+        Outcome::Unsolvable {
+            reachable_states: None,
+        } => println!("no solution"),
+        Outcome::BudgetExceeded { limit, partial } => {
+            let limit = match limit {
+                Limit::Time => "time",
+                Limit::ExpandedNodes => "node",
+                Limit::Memory => "memory",
+            };
+            println!("{limit} limit exceeded, best partial line found:");
+            print_line(&state.replay(&partial), args.format, &game);
         }
+    }
+
+    Ok(())
+}
+
+fn game_config(args: &Args) -> anyhow::Result<GameConfig> {
+    let game = GameConfig {
+        stacks: args.columns,
+        slots: args.slots,
+        ranks: args.ranks,
+        colors: args.colors,
+        pirates: args.pirates,
+    };
+    game.validate().context("invalid variant")?;
+    Ok(game)
+}
+
+fn solver_options(args: &Args, game: GameConfig) -> anyhow::Result<SolverOptions> {
+    anyhow::ensure!(
+        args.weight.is_finite() && args.weight >= 1.0,
+        "--weight must be a finite number of at least 1, not {}",
+        args.weight
+    );
+    Ok(SolverOptions {
+        game,
+        strategy: match args.strategy {
+            Strategy::Astar => SearchStrategy::AStar,
+            Strategy::WeightedAstar => SearchStrategy::WeightedAStar {
+                weight: args.weight,
+            },
+            Strategy::Idastar => SearchStrategy::IdaStar,
+            Strategy::Greedy => SearchStrategy::GreedyBestFirst,
+            Strategy::Dfs => SearchStrategy::DepthFirst,
+        },
+        heuristic: if args.optimal {
+            Heuristic::Admissible
+        } else {
+            Heuristic::Fast
+        },
+        auto_play: if args.auto_play {
+            AutoPlay::Safe
+        } else {
+            AutoPlay::Off
+        },
+        budget: Budget {
+            time: args.time_limit.map(Duration::from_secs_f64),
+            expanded_nodes: args.node_limit,
+            memory: args.memory_limit.map(|mebibytes| mebibytes << 20),
+        },
+        parallelism: match args.parallel {
+            Some(searches) => Parallelism::Portfolio {
+                searches,
+                deterministic: args.deterministic,
+            },
+            None => Parallelism::Off,
+        },
+    })
+}
+
+fn batch(
+    args: &Args,
+    options: &SolverOptions,
+    mut deals: Vec<Deal>,
+    results: &Path,
+    format: RecordFormat,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        args.certificate.is_none(),
+        "--certificate cannot be used with batch"
+    );
+    let (writer, done) = RecordWriter::resume(results, format)?;
+    deals.retain(|deal| !done.contains(&deal.name));
+
+    let total = deals.len();
+    eprintln!("{} deals already recorded, {total} to solve", done.len());
+    let writer = Mutex::new(writer);
+    let solved = AtomicUsize::new(0);
+    solve_deals(
+        &deals,
+        &options.game,
+        |state| {
+            if args.exhaustive {
+                explore(state, &options.game, &options.budget).result
+            } else {
+                solve_with(state, options)
+            }
+        },
+        |record| {
+            writer.lock().unwrap().write(&record)?;
+            let solved = solved.fetch_add(1, Ordering::Relaxed) + 1;
+            eprint!("\r{solved}/{total}");
+            Ok(())
+        },
+    )?;
+    eprintln!();
+    Ok(())
+}
+
+fn find_seeds_command(
+    args: &Args,
+    options: &SolverOptions,
+    seeds: &SeedRange,
+    filter: &SeedFilter,
+    count: Option<usize>,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        args.certificate.is_none(),
+        "--certificate cannot be used with find-seeds"
+    );
+    if filter.min_length.is_some() || filter.max_length.is_some() {
+        // the exhaustive exploration returns the first win it reaches, however long
+        anyhow::ensure!(
+            options.finds_minimal_solutions() && !args.exhaustive,
+            "--min-length and --max-length compare the fewest moves, so they need --optimal with \
+             the astar or idastar strategy, and cannot be used with --exhaustive"
+        );
+    }
+    let printed = AtomicUsize::new(0);
+    let stdout = Mutex::new(std::io::stdout());
+    let summary = find_seeds(
+        seeds,
+        &options.game,
+        filter,
+        |state| {
+            if args.exhaustive {
+                explore(state, &options.game, &options.budget).result
+            } else {
+                solve_with(state, options)
+            }
+        },
+        |found| {
+            let printed = printed.fetch_add(1, Ordering::Relaxed) + 1;
+            if count.is_some_and(|count| printed > count) {
+                return Ok(ControlFlow::Break(()));
+            }
+            let mut stdout = stdout.lock().unwrap();
+            match args.output {
+                Output::Text => writeln!(stdout, "{}", describe_match(&found))?,
+                Output::Json => {
+                    serde_json::to_writer(&mut *stdout, &found)?;
+                    writeln!(stdout)?;
+                }
+            }
+            stdout.flush()?;
+            Ok(if count.is_some_and(|count| printed >= count) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            })
+        },
+    )?;
+
+    eprint!(
+        "{} seeds scanned, {} found",
+        summary.scanned,
+        summary.matched.min(count.unwrap_or(usize::MAX))
+    );
+    if summary.undecided > 0 {
+        eprint!(
+            ", {} left undecided by the search limits",
+            summary.undecided
+        );
+    }
+    eprintln!();
+    Ok(())
+}
+
+fn reverse_seed_command(
+    state: &DenormalizedState,
+    seeds: &SeedRange,
+    game: &GameConfig,
+) -> anyhow::Result<()> {
+    let total = seeds.seed_count();
+    let start = Instant::now();
+    let seeds_per_second = |scanned: u64| scanned as f64 / start.elapsed().as_secs_f64();
+    let found = reverse_seed(state, seeds, game, |scanned| {
+        eprint!(
+            "\r{scanned}/{total} seeds scanned, {:.0} per second",
+            seeds_per_second(scanned)
+        );
+    })?;
+    eprintln!();
+    match found {
+        Some(seed) => {
+            println!("{seed}");
+            Ok(())
+        }
+        None => anyhow::bail!("no seed of the range deals this layout"),
+    }
+}
+
+fn describe_match(found: &SeedMatch) -> String {
+    match (found.solvable, found.length) {
+        (_, Some(length)) => format!("{} won in {length} moves", found.seed),
+        (Some(false), _) => format!("{} cannot be won", found.seed),
+        _ => found.seed.to_string(),
+    }
+}
+
+fn hint(
+    state: &DenormalizedState,
+    outcome: Outcome,
+    minimal: bool,
+    reveal: bool,
+) -> anyhow::Result<()> {
+    let solution = match outcome {
+        Outcome::Solved(solution) => state.replay(&solution),
+        Outcome::Unsolvable { .. } => {
+            println!("no solution: this position cannot be won");
+            return Ok(());
+        }
+        Outcome::BudgetExceeded { .. } => {
+            println!("no solution found within the limits");
+            return Ok(());
+        }
+    };
+    if solution.moves.is_empty() {
+        println!("already won");
+        return Ok(());
+    }
+
+    let total = solution.moves.len();
+    let moves_left = if minimal {
+        format!("{total} moves left, the fewest possible")
     } else {
-        println!("no solution");
+        format!("{total} moves left")
+    };
+    let steps = solution.states.iter().zip(&solution.moves).enumerate();
+    if !reveal {
+        let (_, (step, mv)) = steps.into_iter().next().unwrap();
+        println!("next move: {}", step.describe(*mv));
+        println!("{moves_left}");
+        return Ok(());
     }
 
+    println!("{moves_left}, press Enter for each move or q then Enter to stop");
+    let mut lines = std::io::stdin().lines();
+    for (i, (step, mv)) in steps {
+        print!("{:>3}. {}", i + 1, step.describe(*mv));
+        if i + 1 == total {
+            break;
+        }
+        std::io::stdout().flush()?;
+        match lines.next().transpose()? {
+            Some(line) if line.trim() != "q" => {}
+            _ => break,
+        }
+    }
+    println!();
     Ok(())
 }
+
+fn print_line(solution: &DenormalizedSolution, format: Format, game: &GameConfig) {
+    match format {
+        Format::Board => {
+            for (i, step) in solution.states.iter().enumerate() {
+                println!();
+                println!("==============");
+                println!();
+                println!("STEP {i}:");
+                println!("{}", step.display_with(game));
+            }
+        }
+        Format::Moves => {
+            for (i, (step, mv)) in solution.states.iter().zip(&solution.moves).enumerate() {
+                println!("{:>3}. {}", i + 1, step.describe(*mv));
+            }
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Compact fixed-size encoding of normalized states, used as the key of the searched states.
+
+use crate::{
+    collection::BTreeMultiSet,
+    config::{GameConfig, MAX_RANKS},
+    state::{
+        Beast, Card, CardColor, CardNumber, CardOrBundle, CardStack, NormalCard, Output,
+        PlaceHolder, PlaceHolders, State,
+    },
+};
+
+/// A [`State`] packed into `WORDS` 64-bit words, with no heap allocation.
+///
+/// Stacks and placeholders are written in the iteration order of the state, so two states are
+/// equal exactly when their packed forms are. The layout, from the lowest bit:
+/// - the number of stacks (4 bits) and of placeholders (3 bits),
+/// - the output of each of the four colors (4 bits each),
+/// - each placeholder (a card code and one more bit: 0 when empty, a card code, or a bundle
+///   code),
+/// - each stack as its card codes from the bottom, followed by a 0.
+///
+/// The default form takes 48 bytes with 6-bit card codes and holds the states of every variant.
+/// The [`CompactPackedState`] takes 32 bytes with 5-bit card codes, enough for decks of up to
+/// three colors: a full standard deck of 36 cards on 6 stacks with 3 placeholders takes 251 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackedState<const WORDS: usize = WIDE_WORDS>([u64; WORDS]);
+
+/// The packed form searched for the standard game, see [`PackedState`].
+pub type CompactPackedState = PackedState<COMPACT_WORDS>;
+
+/// Words of a [`CompactPackedState`].
+pub(crate) const COMPACT_WORDS: usize = 4;
+/// Words of the default [`PackedState`].
+pub(crate) const WIDE_WORDS: usize = 6;
+
+const OUTPUT_BITS: u32 = 4;
+const STACK_COUNT_BITS: u32 = 4;
+const PLACEHOLDER_COUNT_BITS: u32 = 3;
+
+/// Colors in the order of their [`CardColor`] discriminant.
+const COLORS: [CardColor; 4] = [
+    CardColor::Red,
+    CardColor::Teal,
+    CardColor::Blue,
+    CardColor::Green,
+];
+const BEASTS: [Beast; 3] = [Beast::Whale, Beast::Tentacle, Beast::Ship];
+
+/// Code of the first numbered card, 0 ending a stack.
+const FIRST_NORMAL_CODE: u64 = 2 + BEASTS.len() as u64;
+
+pub(crate) fn card_code(card: &Card) -> u64 {
+    match card {
+        Card::Pirate => 1,
+        Card::Beast(beast) => 2 + *beast as u64,
+        Card::Normal(NormalCard { number, color }) => {
+            FIRST_NORMAL_CODE + *color as u64 * MAX_RANKS as u64 + (number.0 - 1) as u64
+        }
+    }
+}
+
+/// Code of a placeholder in the default [`PackedState`]: 0 when empty, the code of its card, or a
+/// bundle code.
+pub(crate) fn placeholder_code(hole: &PlaceHolder) -> u64 {
+    PackedState::<WIDE_WORDS>::placeholder_code(hole)
+}
+
+fn code_card(code: u64) -> Card {
+    match code {
+        1 => Card::Pirate,
+        code if code < FIRST_NORMAL_CODE => Card::Beast(BEASTS[code as usize - 2]),
+        code => {
+            let index = (code - FIRST_NORMAL_CODE) as usize;
+            Card::Normal(NormalCard {
+                number: CardNumber((index % MAX_RANKS as usize) as u8 + 1),
+                color: COLORS[index / MAX_RANKS as usize],
+            })
+        }
+    }
+}
+
+/// Whether every state of the variant described by `config` can be packed into `WORDS` words,
+/// which holds when the codes of its cards fit the width of that form and its whole deck can be
+/// packed on its stacks.
+pub(crate) fn fits<const WORDS: usize>(config: &GameConfig) -> bool {
+    let card_codes = FIRST_NORMAL_CODE + (config.colors * MAX_RANKS as usize) as u64;
+    let bits = STACK_COUNT_BITS
+        + PLACEHOLDER_COUNT_BITS
+        + COLORS.len() as u32 * OUTPUT_BITS
+        + config.slots as u32 * PackedState::<WORDS>::PLACEHOLDER_BITS
+        + (config.card_count() + config.stacks) as u32 * PackedState::<WORDS>::CARD_BITS;
+    card_codes <= 1 << PackedState::<WORDS>::CARD_BITS && bits <= 64 * WORDS as u32
+}
+
+impl<const WORDS: usize> PackedState<WORDS> {
+    /// Width of a card code: 5 bits in the compact form, 6 bits in wider ones.
+    const CARD_BITS: u32 = if WORDS > COMPACT_WORDS { 6 } else { 5 };
+    const PLACEHOLDER_BITS: u32 = Self::CARD_BITS + 1;
+    /// Placeholder code of the first bundle, past every card code.
+    const FIRST_BUNDLE_CODE: u64 = 1 << Self::CARD_BITS;
+
+    /// Code of a placeholder: 0 when empty, the code of its card, or a bundle code.
+    fn placeholder_code(hole: &PlaceHolder) -> u64 {
+        match &hole.0 {
+            None => 0,
+            Some(CardOrBundle::Card(card)) => card_code(card),
+            Some(CardOrBundle::BeastBundle(beast)) => Self::FIRST_BUNDLE_CODE + *beast as u64,
+        }
+    }
+}
+
+struct BitWriter<const WORDS: usize> {
+    words: [u64; WORDS],
+    position: u32,
+}
+
+impl<const WORDS: usize> BitWriter<WORDS> {
+    fn write(&mut self, value: u64, bits: u32) {
+        assert!(value < 1 << bits, "code too large to be packed");
+        let word = (self.position / 64) as usize;
+        let offset = self.position % 64;
+        assert!(
+            self.position + bits <= 64 * self.words.len() as u32,
+            "state too large to be packed"
+        );
+        self.words[word] |= value << offset;
+        if offset + bits > 64 {
+            self.words[word + 1] |= value >> (64 - offset);
+        }
+        self.position += bits;
+    }
+}
+
+struct BitReader<'a, const WORDS: usize> {
+    words: &'a [u64; WORDS],
+    position: u32,
+}
+
+impl<const WORDS: usize> BitReader<'_, WORDS> {
+    fn read(&mut self, bits: u32) -> u64 {
+        let word = (self.position / 64) as usize;
+        let offset = self.position % 64;
+        let mut value = self.words[word] >> offset;
+        if offset + bits > 64 {
+            value |= self.words[word + 1] << (64 - offset);
+        }
+        self.position += bits;
+        value & ((1 << bits) - 1)
+    }
+}
+
+/// Packs a state given its stacks and placeholders in normalized order.
+///
+/// # Panics
+///
+/// If the state holds more cards, stacks or placeholders than [`fits`] allows.
+pub(crate) fn pack<'a, const WORDS: usize>(
+    stacks: impl Iterator<Item = &'a CardStack> + Clone,
+    holes: impl Iterator<Item = &'a PlaceHolder> + Clone,
+    output: &Output,
+) -> PackedState<WORDS> {
+    let mut writer = BitWriter {
+        words: [0; WORDS],
+        position: 0,
+    };
+
+    writer.write(stacks.clone().count() as u64, STACK_COUNT_BITS);
+    writer.write(holes.clone().count() as u64, PLACEHOLDER_COUNT_BITS);
+    for number in output.by_color {
+        writer.write(number.0 as u64, OUTPUT_BITS);
+    }
+    for hole in holes {
+        writer.write(
+            PackedState::<WORDS>::placeholder_code(hole),
+            PackedState::<WORDS>::PLACEHOLDER_BITS,
+        );
+    }
+    for stack in stacks {
+        for card in &stack.cards {
+            writer.write(card_code(card), PackedState::<WORDS>::CARD_BITS);
+        }
+        writer.write(0, PackedState::<WORDS>::CARD_BITS);
+    }
+
+    PackedState(writer.words)
+}
+
+impl State {
+    /// Packs this state into the default form, which holds every variant, see [`PackedState`].
+    ///
+    /// # Panics
+    ///
+    /// If the state holds more cards, stacks or placeholders than a valid [`GameConfig`] allows.
+    pub fn pack(&self) -> PackedState {
+        self.pack_as()
+    }
+
+    /// Packs this state into `WORDS` words, see [`PackedState`].
+    ///
+    /// # Panics
+    ///
+    /// If the state holds more cards, stacks or placeholders than the form allows, or cards of a
+    /// fourth color for the [`CompactPackedState`].
+    pub fn pack_as<const WORDS: usize>(&self) -> PackedState<WORDS> {
+        pack(
+            self.board.iter(),
+            self.placeholders.holes.iter(),
+            &self.output,
+        )
+    }
+}
+
+impl<const WORDS: usize> PackedState<WORDS> {
+    /// Rebuilds the packed state.
+    pub fn unpack(&self) -> State {
+        let mut reader = BitReader {
+            words: &self.0,
+            position: 0,
+        };
+
+        let stack_count = reader.read(STACK_COUNT_BITS);
+        let placeholder_count = reader.read(PLACEHOLDER_COUNT_BITS);
+        let mut output = Output::default();
+        for number in &mut output.by_color {
+            *number = CardNumber(reader.read(OUTPUT_BITS) as u8);
+        }
+        let holes = (0..placeholder_count)
+            .map(|_| {
+                PlaceHolder(match reader.read(Self::PLACEHOLDER_BITS) {
+                    0 => None,
+                    code if code < Self::FIRST_BUNDLE_CODE => {
+                        Some(CardOrBundle::Card(code_card(code)))
+                    }
+                    code => Some(CardOrBundle::BeastBundle(
+                        BEASTS[(code - Self::FIRST_BUNDLE_CODE) as usize],
+                    )),
+                })
+            })
+            .collect::<BTreeMultiSet<_>>();
+        let board = (0..stack_count)
+            .map(|_| CardStack {
+                cards: std::iter::from_fn(|| match reader.read(Self::CARD_BITS) {
+                    0 => None,
+                    code => Some(code_card(code)),
+                })
+                .collect(),
+            })
+            .collect();
+
+        State {
+            placeholders: PlaceHolders { holes },
+            output,
+            board,
+        }
+    }
+}
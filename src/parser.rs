@@ -1,8 +1,19 @@
-use std::str::FromStr;
+//! Text parsing of cards, stacks and whole deals.
+
+use std::{fmt::Display, io::BufRead, str::FromStr};
 
 use anyhow::{Context, anyhow};
+use itertools::Itertools;
+use thiserror::Error;
 
-use crate::state::{Beast, Card, CardColor, CardNumber, CardStack, NormalCard};
+use crate::{
+    config::GameConfig,
+    denormalized::{DenormalizedState, PlaceHolders},
+    state::{
+        Beast, Card, CardColor, CardNumber, CardOrBundle, CardStack, NormalCard, Output,
+        PlaceHolder,
+    },
+};
 
 impl FromStr for CardColor {
     type Err = anyhow::Error;
@@ -12,6 +23,7 @@ impl FromStr for CardColor {
             "t" => Ok(CardColor::Teal),
             "r" => Ok(CardColor::Red),
             "b" => Ok(CardColor::Blue),
+            "g" => Ok(CardColor::Green),
             invalid_color => Err(anyhow!("invalid card color: {invalid_color}")),
         }
     }
@@ -59,17 +71,319 @@ impl FromStr for CardStack {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let cards: [Card; 6] = s
-            .split(" ")
+        if s.trim() == EMPTY {
+            return Ok(Self { cards: vec![] });
+        }
+
+        let cards = s
+            .split_whitespace()
             .map(|c| c.parse())
-            .take(6)
             .collect::<Result<Vec<_>, _>>()
-            .context("parsing stack")?
-            .try_into()
-            .map_err(|_| anyhow!("stack size must be 6"))?;
+            .context("parsing stack")?;
+
+        Ok(Self { cards })
+    }
+}
+
+impl FromStr for PlaceHolder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == EMPTY {
+            return Ok(Self(None));
+        }
+
+        let content = match s.strip_prefix(BUNDLE_PREFIX) {
+            Some(beast) => match beast.parse()? {
+                Card::Beast(beast) => CardOrBundle::BeastBundle(beast),
+                _ => return Err(anyhow!("only beasts can be bundled: {s}")),
+            },
+            None => CardOrBundle::Card(s.parse()?),
+        };
+
+        Ok(Self(Some(content)))
+    }
+}
+
+/// Token standing for an empty stack or placeholder.
+const EMPTY: &str = "-";
+
+const BUNDLE_PREFIX: char = '#';
+
+const COMMENT_PREFIX: &str = "//";
+
+const PLACEHOLDERS_PREFIX: &str = "slots:";
+
+const OUTPUT_PREFIX: &str = "output:";
+
+/// Error returned by [`read_from_with`], locating the offending token.
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("line {line}, column {column}: {reason} `{token}`{suggestion}")]
+    InvalidToken {
+        line: usize,
+        column: usize,
+        token: String,
+        reason: &'static str,
+        suggestion: Suggestion,
+    },
+    #[error("line {line}: expected {expected} placeholders, found {found}")]
+    PlaceHolderCount {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("expected {expected} card stacks, found {found}")]
+    StackCount { expected: usize, found: usize },
+    #[error("line {line}: read error")]
+    Read {
+        line: usize,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Closest valid spelling of a mistyped token, if any is close enough.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion(pub Option<String>);
+
+impl Display for Suggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(suggestion) => write!(f, ", did you mean `{suggestion}`?"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Suggestion {
+    /// Suggests the candidate at the smallest edit distance from `token`, counting an adjacent
+    /// transposition as a single edit, as long as at most two edits are needed.
+    fn nearest(token: &str, candidates: impl IntoIterator<Item = String>) -> Self {
+        let token = token.to_ascii_lowercase();
+        Self(
+            candidates
+                .into_iter()
+                .map(|candidate| (edit_distance(&token, &candidate), candidate))
+                .filter(|(distance, _)| *distance <= 2)
+                .min_by_key(|(distance, _)| *distance)
+                .map(|(_, candidate)| candidate),
+        )
+    }
+}
+
+/// Optimal string alignment distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect_vec();
+    let b = b.chars().collect_vec();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = (a[i - 1] != b[j - 1]) as usize;
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + substitution);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+fn normal_card_spellings(config: &GameConfig) -> impl Iterator<Item = String> + use<> {
+    config
+        .card_numbers()
+        .cartesian_product(config.card_colors().sorted())
+        .map(|(number, color)| Card::Normal(NormalCard { number, color }).to_string())
+}
+
+fn card_spellings(config: &GameConfig) -> impl Iterator<Item = String> + use<> {
+    ["p", "w", "t", "s"]
+        .map(String::from)
+        .into_iter()
+        .chain(normal_card_spellings(config))
+}
+
+fn placeholder_spellings(config: &GameConfig) -> impl Iterator<Item = String> + use<> {
+    [EMPTY.to_string()]
+        .into_iter()
+        .chain(["w", "t", "s"].map(|beast| format!("{BUNDLE_PREFIX}{beast}")))
+        .chain(card_spellings(config))
+}
+
+/// A whitespace separated token along with its 1-based column.
+struct Token<'a> {
+    column: usize,
+    text: &'a str,
+}
+
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut token_start = None;
+    for (index, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        match (token_start, c.is_whitespace()) {
+            (None, false) => token_start = Some(index),
+            (Some(start), true) => {
+                tokens.push(Token {
+                    column: line[..start].chars().count() + 1,
+                    text: &line[start..index],
+                });
+                token_start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
 
-        Ok(Self {
-            cards: cards.to_vec(),
+impl Token<'_> {
+    fn parse<T: FromStr>(
+        &self,
+        line: usize,
+        reason: &'static str,
+        spellings: impl IntoIterator<Item = String>,
+    ) -> Result<T, ParseError> {
+        self.text.parse().map_err(|_| ParseError::InvalidToken {
+            line,
+            column: self.column,
+            token: self.text.to_string(),
+            reason,
+            suggestion: Suggestion::nearest(self.text, spellings),
         })
     }
 }
+
+/// Reads a standard game state, see [`read_from_with`].
+pub fn read_from(read: impl BufRead) -> Result<DenormalizedState, ParseError> {
+    read_from_with(read, &GameConfig::STANDARD)
+}
+
+/// Reads a game state with the table of `config`, one card stack per line from the bottom card
+/// to the exposed one, with `-` standing for an empty stack.
+///
+/// Two optional lines describe a game in progress:
+/// - `slots: 3r - #w` gives the content of each placeholder, with `-` for an empty one and `#`
+///   before a beast for a promoted beast bundle.
+/// - `output: 3r 2b` gives the highest card already sent to the output for each color, written
+///   at most once, colors left out having none.
+///
+/// Tokens can be separated by any amount of whitespace, blank lines are ignored and `//` starts a
+/// comment running to the end of the line.
+pub fn read_from_with(
+    read: impl BufRead,
+    config: &GameConfig,
+) -> Result<DenormalizedState, ParseError> {
+    let mut placeholders = PlaceHolders::empty(config.slots);
+    let mut output = Output::default();
+    let mut board = vec![];
+
+    for (line_number, line) in read.lines().enumerate() {
+        let line_number = line_number + 1;
+        let line = line.map_err(|source| ParseError::Read {
+            line: line_number,
+            source,
+        })?;
+        let line = match line.find(COMMENT_PREFIX) {
+            Some(comment_start) => &line[..comment_start],
+            None => &line,
+        };
+
+        let mut tokens = tokenize(line).into_iter().peekable();
+        let Some(first) = tokens.peek() else {
+            continue;
+        };
+
+        match first.text {
+            PLACEHOLDERS_PREFIX => {
+                let holes: Vec<PlaceHolder> = tokens
+                    .skip(1)
+                    .map(|token| {
+                        token.parse(
+                            line_number,
+                            "invalid placeholder",
+                            placeholder_spellings(config),
+                        )
+                    })
+                    .try_collect()?;
+                if holes.len() != config.slots {
+                    return Err(ParseError::PlaceHolderCount {
+                        line: line_number,
+                        expected: config.slots,
+                        found: holes.len(),
+                    });
+                }
+                placeholders = PlaceHolders { holes };
+            }
+            OUTPUT_PREFIX => {
+                output = Output::default();
+                let mut colors = vec![];
+                for token in tokens.skip(1) {
+                    let card: Card = token.parse(
+                        line_number,
+                        "invalid output card",
+                        normal_card_spellings(config),
+                    )?;
+                    let Card::Normal(NormalCard { number, color }) = card else {
+                        return Err(ParseError::InvalidToken {
+                            line: line_number,
+                            column: token.column,
+                            token: token.text.to_string(),
+                            reason: "invalid output card",
+                            suggestion: Suggestion(None),
+                        });
+                    };
+                    if colors.contains(&color) {
+                        return Err(ParseError::InvalidToken {
+                            line: line_number,
+                            column: token.column,
+                            token: token.text.to_string(),
+                            reason: "repeated output color",
+                            suggestion: Suggestion(None),
+                        });
+                    }
+                    colors.push(color);
+                    output[&color] = number;
+                }
+            }
+            EMPTY => {
+                if let Some(token) = tokens.nth(1) {
+                    return Err(ParseError::InvalidToken {
+                        line: line_number,
+                        column: token.column,
+                        token: token.text.to_string(),
+                        reason: "unexpected card after an empty stack",
+                        suggestion: Suggestion(None),
+                    });
+                }
+                board.push(CardStack { cards: vec![] });
+            }
+            _ => {
+                let cards = tokens
+                    .map(|token| token.parse(line_number, "invalid card", card_spellings(config)))
+                    .try_collect()?;
+                board.push(CardStack { cards });
+            }
+        }
+    }
+
+    if board.len() != config.stacks {
+        return Err(ParseError::StackCount {
+            expected: config.stacks,
+            found: board.len(),
+        });
+    }
+
+    Ok(DenormalizedState {
+        placeholders,
+        output,
+        board,
+    })
+}
@@ -0,0 +1,432 @@
+//! Keyboard-driven terminal player, with undo, redo and help from the solver.
+
+use std::io::{Write, stdout};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+use itertools::Itertools;
+
+use crate::{
+    denormalized::DenormalizedState,
+    game::{Game, Spot},
+    printer::OUTPUT_COLORS,
+    solver::{Outcome, SolverOptions, solve_with},
+    state::{Card, CardColor, CardOrBundle, NormalCard},
+};
+
+/// Width of a card on screen, margins included.
+const CELL_WIDTH: u16 = 4;
+/// Screen line of the slots and outputs.
+const TOP_ROW: u16 = 2;
+/// Screen line of the first card of each column.
+const BOARD_ROW: u16 = 4;
+
+const HELP: &str = "arrows move, space picks up and drops, +/- cards to move, o output, \
+                    u undo, r redo, ? hint, w winnable, q quit";
+
+/// Restores the terminal when dropped, even after an error.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> std::io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Cards picked up, waiting to be dropped.
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+    spot: Spot,
+    /// Number of cards taken from a column, the most that fit the destination when unset.
+    count: Option<usize>,
+}
+
+struct Player {
+    game: Game,
+    title: String,
+    options: SolverOptions,
+    /// Spot under the cursor: slots and outputs on the top row, columns below.
+    cursor: Spot,
+    selection: Option<Selection>,
+    message: String,
+}
+
+fn card_color(card: &Card) -> Color {
+    match card {
+        Card::Normal(card) => match card.color {
+            CardColor::Red => Color::Red,
+            CardColor::Teal => Color::Cyan,
+            CardColor::Blue => Color::Blue,
+            CardColor::Green => Color::Green,
+        },
+        _ => Color::White,
+    }
+}
+
+impl Player {
+    fn state(&self) -> &DenormalizedState {
+        self.game.state()
+    }
+
+    /// Number of cards highlighted at the top of the selected column.
+    fn selected_count(&self, column: usize) -> usize {
+        match self.selection {
+            Some(Selection {
+                spot: Spot::Column(selected),
+                count,
+            }) if selected == column => count.unwrap_or_else(|| {
+                self.game
+                    .movable_counts(column)
+                    .last()
+                    .copied()
+                    .unwrap_or(1)
+            }),
+            _ => 0,
+        }
+    }
+
+    fn draw_cell(
+        &self,
+        out: &mut impl Write,
+        x: u16,
+        y: u16,
+        text: &str,
+        color: Color,
+        highlight: Option<Attribute>,
+    ) -> std::io::Result<()> {
+        queue!(out, cursor::MoveTo(x * CELL_WIDTH + 1, y))?;
+        if let Some(attribute) = highlight {
+            queue!(out, SetAttribute(attribute))?;
+        }
+        queue!(
+            out,
+            SetForegroundColor(color),
+            Print(format!("{text:^width$}", width = CELL_WIDTH as usize - 1)),
+            ResetColor,
+            SetAttribute(Attribute::Reset)
+        )
+    }
+
+    fn draw(&self) -> std::io::Result<()> {
+        let mut out = stdout();
+        queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(1, 0))?;
+        queue!(
+            out,
+            Print(format!(
+                "{}, {} moves played",
+                self.title,
+                self.game.moves_played()
+            ))
+        )?;
+
+        let state = self.state();
+        let cursor_on = |spot: Spot| (self.cursor == spot).then_some(Attribute::Reverse);
+
+        for (x, hole) in state.placeholders.holes.iter().enumerate() {
+            let (text, color) = match hole.0 {
+                Some(CardOrBundle::Card(card)) => (card.to_string(), card_color(&card)),
+                Some(CardOrBundle::BeastBundle(_)) => ("##".to_string(), Color::White),
+                None => ("--".to_string(), Color::DarkGrey),
+            };
+            let spot = Spot::Slot(x);
+            let highlight = if self
+                .selection
+                .is_some_and(|selection| selection.spot == spot)
+            {
+                Some(Attribute::Underlined)
+            } else {
+                cursor_on(spot)
+            };
+            self.draw_cell(&mut out, x as u16, TOP_ROW, &text, color, highlight)?;
+        }
+
+        let slots = state.placeholders.holes.len();
+        let colors = OUTPUT_COLORS
+            .into_iter()
+            .filter(|color| self.options.game.card_colors().contains(color));
+        for (x, color) in colors.enumerate() {
+            let number = state.output[&color];
+            let (text, color) = if number.0 == 0 {
+                ("--".to_string(), Color::DarkGrey)
+            } else {
+                let card = Card::Normal(NormalCard { color, number });
+                (card.to_string(), card_color(&card))
+            };
+            let highlight = cursor_on(Spot::Output);
+            self.draw_cell(
+                &mut out,
+                (x + slots) as u16,
+                TOP_ROW,
+                &text,
+                color,
+                highlight,
+            )?;
+        }
+
+        let mut bottom = BOARD_ROW;
+        for (x, stack) in state.board.iter().enumerate() {
+            let spot = Spot::Column(x);
+            let selected = self.selected_count(x);
+            if stack.cards.is_empty() {
+                self.draw_cell(
+                    &mut out,
+                    x as u16,
+                    BOARD_ROW,
+                    "--",
+                    Color::DarkGrey,
+                    cursor_on(spot),
+                )?;
+            }
+            for (y, card) in stack.cards.iter().enumerate() {
+                let from_top = stack.cards.len() - y;
+                let highlight = if from_top <= selected {
+                    Some(Attribute::Underlined)
+                } else if from_top == 1 {
+                    cursor_on(spot)
+                } else {
+                    None
+                };
+                let y = BOARD_ROW + y as u16;
+                self.draw_cell(
+                    &mut out,
+                    x as u16,
+                    y,
+                    &card.to_string(),
+                    card_color(card),
+                    highlight,
+                )?;
+            }
+            bottom = bottom.max(BOARD_ROW + stack.cards.len() as u16);
+        }
+
+        queue!(
+            out,
+            cursor::MoveTo(1, bottom + 1),
+            Print(&self.message),
+            cursor::MoveTo(1, bottom + 3),
+            SetForegroundColor(Color::DarkGrey),
+            Print(HELP),
+            ResetColor
+        )?;
+        out.flush()
+    }
+
+    fn move_cursor(&mut self, code: KeyCode) {
+        let slots = self.state().placeholders.holes.len();
+        let columns = self.state().board.len();
+        let (top, index) = match self.cursor {
+            Spot::Slot(slot) => (true, slot),
+            Spot::Output => (true, slots),
+            Spot::Column(column) => (false, column),
+        };
+        // the slots and the output on top, the columns below
+        let positions = |top| if top { slots + 1 } else { columns };
+        let (top, index) = match code {
+            KeyCode::Left => (top, (index + positions(top) - 1) % positions(top)),
+            KeyCode::Right => (top, (index + 1) % positions(top)),
+            KeyCode::Up | KeyCode::Down => (!top, index.min(positions(!top) - 1)),
+            _ => (top, index),
+        };
+        self.cursor = match (top, index) {
+            (true, slot) if slot < slots => Spot::Slot(slot),
+            (true, _) => Spot::Output,
+            (false, column) => Spot::Column(column),
+        };
+    }
+
+    fn holds_card(&self, spot: Spot) -> bool {
+        match spot {
+            Spot::Column(column) => !self.state().board[column].cards.is_empty(),
+            Spot::Slot(slot) => matches!(
+                self.state().placeholders.holes[slot].0,
+                Some(CardOrBundle::Card(_))
+            ),
+            Spot::Output => false,
+        }
+    }
+
+    /// Picks up the cards under the cursor, or drops the selected ones there.
+    fn select(&mut self) {
+        match self.selection {
+            None if self.holds_card(self.cursor) => {
+                self.selection = Some(Selection {
+                    spot: self.cursor,
+                    count: None,
+                });
+            }
+            None => self.message = "nothing to pick up here".to_string(),
+            Some(selection) if selection.spot == self.cursor => self.selection = None,
+            Some(selection) => self.drop_selection(selection, self.cursor),
+        }
+    }
+
+    fn drop_selection(&mut self, selection: Selection, to: Spot) {
+        self.selection = None;
+        let Some(mv) = self.game.find_move(selection.spot, to, selection.count) else {
+            self.message = "illegal move".to_string();
+            return;
+        };
+        let description = self.state().describe(mv).to_string();
+        self.game.play(mv).expect("found moves are legal");
+        self.message = if self.game.is_won() {
+            format!("won in {} moves!", self.game.moves_played())
+        } else {
+            description
+        };
+    }
+
+    fn change_count(&mut self, more: bool) {
+        let Some(Selection {
+            spot: Spot::Column(column),
+            count,
+        }) = self.selection
+        else {
+            self.message = "pick up cards from a column first".to_string();
+            return;
+        };
+        let counts = self.game.movable_counts(column);
+        let current = count.unwrap_or_else(|| counts.last().copied().unwrap_or(1));
+        let next = if more {
+            counts.iter().find(|count| **count > current)
+        } else {
+            counts.iter().rev().find(|count| **count < current)
+        };
+        if let Some(&next) = next {
+            self.selection = Some(Selection {
+                spot: Spot::Column(column),
+                count: Some(next),
+            });
+        }
+    }
+
+    /// Solves the current position, telling the player it may take a while.
+    fn solve(&mut self) -> std::io::Result<Outcome> {
+        self.message = "thinking...".to_string();
+        self.draw()?;
+        Ok(solve_with(&self.state().normalize(), &self.options).outcome)
+    }
+
+    fn hint(&mut self) -> std::io::Result<()> {
+        self.message = match self.solve()? {
+            Outcome::Solved(solution) => match self.state().replay(&solution).moves.first() {
+                Some(&mv) => format!(
+                    "hint: {}, {} moves left",
+                    self.state().describe(mv),
+                    solution.moves.len()
+                ),
+                None => "already won".to_string(),
+            },
+            Outcome::Unsolvable { .. } => "no hint: this position cannot be won".to_string(),
+            Outcome::BudgetExceeded { .. } => "no hint found within the limits".to_string(),
+        };
+        Ok(())
+    }
+
+    fn check_winnable(&mut self) -> std::io::Result<()> {
+        self.message = match self.solve()? {
+            Outcome::Solved(solution) => {
+                format!("still winnable, in {} moves", solution.moves.len())
+            }
+            Outcome::Unsolvable { .. } => "not winnable any more, try undoing".to_string(),
+            Outcome::BudgetExceeded { .. } => {
+                "unknown, no solution found within the limits".to_string()
+            }
+        };
+        Ok(())
+    }
+
+    /// Handles a key press, returning false to quit.
+    fn handle(&mut self, key: KeyEvent) -> std::io::Result<bool> {
+        self.message.clear();
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('q') => return Ok(false),
+            KeyCode::Char('c') if control => return Ok(false),
+            KeyCode::Char('z') if control => self.undo(),
+            KeyCode::Char('y') if control => self.redo(),
+            code @ (KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down) => {
+                self.move_cursor(code)
+            }
+            KeyCode::Char('h') => self.move_cursor(KeyCode::Left),
+            KeyCode::Char('l') => self.move_cursor(KeyCode::Right),
+            KeyCode::Char('k') => self.move_cursor(KeyCode::Up),
+            KeyCode::Char('j') => self.move_cursor(KeyCode::Down),
+            KeyCode::Char(digit @ '1'..='9')
+                if digit as usize - ('1' as usize) < self.state().board.len() =>
+            {
+                self.cursor = Spot::Column(digit as usize - '1' as usize)
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => self.select(),
+            KeyCode::Esc => self.selection = None,
+            KeyCode::Char('+' | '=') => self.change_count(true),
+            KeyCode::Char('-') => self.change_count(false),
+            KeyCode::Char('o') => {
+                let selection = self.selection.unwrap_or(Selection {
+                    spot: self.cursor,
+                    count: None,
+                });
+                self.drop_selection(selection, Spot::Output);
+            }
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('r') => self.redo(),
+            KeyCode::Char('?') => self.hint()?,
+            KeyCode::Char('w') => self.check_winnable()?,
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn undo(&mut self) {
+        self.selection = None;
+        self.message = match self.game.undo() {
+            Ok(_) => "move taken back".to_string(),
+            Err(error) => error.to_string(),
+        };
+    }
+
+    fn redo(&mut self) {
+        self.selection = None;
+        let state = self.state().clone();
+        self.message = match self.game.redo() {
+            Ok(mv) => state.describe(mv).to_string(),
+            Err(error) => error.to_string(),
+        };
+    }
+}
+
+/// Plays `state` in the terminal until the player quits. `title` names the deal, and `options`
+/// configure the searches run for hints and winnability checks.
+pub fn run(state: DenormalizedState, title: String, options: SolverOptions) -> anyhow::Result<()> {
+    let _terminal = RawTerminal::enter()?;
+    let mut player = Player {
+        game: Game::new(state),
+        title,
+        options,
+        cursor: Spot::Column(0),
+        selection: None,
+        message: String::new(),
+    };
+    loop {
+        player.draw()?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Press && !player.handle(key)? {
+            return Ok(());
+        }
+    }
+}
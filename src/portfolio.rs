@@ -0,0 +1,142 @@
+//! Races several searches of the same deal on the [`rayon`] thread pool, see
+//! [`Parallelism::Portfolio`].
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    solver::{
+        AutoPlay, Outcome, Parallelism, SearchStrategy, SolveResult, SolverOptions, Statistics,
+        search_with,
+    },
+    state::State,
+};
+
+/// Strategies raced after the requested one, roughly from the fastest to the shortest solutions.
+const STRATEGIES: [SearchStrategy; 5] = [
+    SearchStrategy::DepthFirst,
+    SearchStrategy::GreedyBestFirst,
+    SearchStrategy::WeightedAStar { weight: 5.0 },
+    SearchStrategy::WeightedAStar { weight: 2.0 },
+    SearchStrategy::AStar,
+];
+
+/// Strategies returning minimal solutions, see [`SolverOptions::finds_minimal_solutions`].
+const MINIMAL_STRATEGIES: [SearchStrategy; 2] = [SearchStrategy::AStar, SearchStrategy::IdaStar];
+
+/// The options of each search, the requested ones first, with the budget shared between them.
+fn members(options: &SolverOptions, searches: usize) -> Vec<SolverOptions> {
+    let requested = SolverOptions {
+        parallelism: Parallelism::Off,
+        ..*options
+    };
+    let strategies = if requested.finds_minimal_solutions() {
+        &MINIMAL_STRATEGIES[..]
+    } else {
+        &STRATEGIES[..]
+    };
+
+    let mut members = vec![requested];
+    for &strategy in strategies {
+        for auto_play in [AutoPlay::Safe, AutoPlay::Off] {
+            let member = SolverOptions {
+                strategy,
+                auto_play,
+                ..requested
+            };
+            if !members.contains(&member) {
+                members.push(member);
+            }
+        }
+    }
+    members.truncate(searches);
+
+    let count = members.len();
+    for member in &mut members {
+        let budget = &mut member.budget;
+        budget.expanded_nodes = budget.expanded_nodes.map(|limit| limit.div_ceil(count));
+        budget.memory = budget.memory.map(|limit| limit.div_ceil(count));
+    }
+    members
+}
+
+const NO_WINNER: usize = usize::MAX;
+
+/// Which search ended the race, by index in [`members`].
+struct Race {
+    deterministic: bool,
+    winner: AtomicUsize,
+}
+
+impl Race {
+    /// Whether the search at `index` can stop, its result being ignored.
+    fn cancels(&self, index: usize) -> bool {
+        let winner = self.winner.load(Ordering::Relaxed);
+        if self.deterministic {
+            winner < index
+        } else {
+            winner != NO_WINNER && winner != index
+        }
+    }
+
+    /// Records that the search at `index` solved the deal or proved it unsolvable.
+    fn finish(&self, index: usize) {
+        if self.deterministic {
+            self.winner.fetch_min(index, Ordering::Relaxed);
+        } else {
+            let _ = self.winner.compare_exchange(
+                NO_WINNER,
+                index,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+        }
+    }
+}
+
+pub(crate) fn solve(
+    start: &State,
+    options: &SolverOptions,
+    searches: usize,
+    deterministic: bool,
+) -> SolveResult {
+    let start_time = Instant::now();
+    let race = Race {
+        deterministic,
+        winner: AtomicUsize::new(NO_WINNER),
+    };
+
+    let mut results = members(options, searches)
+        .par_iter()
+        .with_max_len(1)
+        .enumerate()
+        .map(|(index, member)| {
+            let cancelled = || race.cancels(index);
+            let result = search_with(start, member, Some(&cancelled));
+            if !matches!(result.outcome, Outcome::BudgetExceeded { .. }) {
+                race.finish(index);
+            }
+            result
+        })
+        .collect::<Vec<_>>();
+
+    let expanded_nodes = results
+        .iter()
+        .map(|result| result.statistics.expanded_nodes)
+        .sum();
+    let winner = match race.winner.into_inner() {
+        NO_WINNER => 0,
+        winner => winner,
+    };
+    SolveResult {
+        outcome: results.swap_remove(winner).outcome,
+        statistics: Statistics {
+            expanded_nodes,
+            elapsed: start_time.elapsed(),
+        },
+    }
+}
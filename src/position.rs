@@ -0,0 +1,326 @@
+//! Mutable game state for searches playing and taking back moves in place.
+
+use itertools::Itertools;
+
+use crate::{
+    actions::{CardSource, Move},
+    config::GameConfig,
+    packed::{self, PackedState},
+    rules,
+    state::{
+        Card, CardNumber, CardOrBundle, CardStack, Layout, NormalCard, Output, PlaceHolder,
+        PlaceHolders, State,
+    },
+    zobrist,
+};
+
+/// A game state played on in place with [`Position::apply`] and [`Position::undo`], keeping its
+/// Zobrist hash up to date one card at a time instead of cloning and rehashing a [`State`].
+///
+/// Stacks and placeholders never change places, so moves refer to the same positions throughout
+/// a line of play, starting from the iteration order of the state the position was made from.
+#[derive(Debug, Clone)]
+pub struct Position {
+    board: Vec<CardStack>,
+    holes: Vec<PlaceHolder>,
+    output: Output,
+    /// [`zobrist::stack_key`] of each stack.
+    stack_keys: Vec<u64>,
+    hash: u64,
+}
+
+/// What [`Position::undo`] needs to take back a move.
+#[derive(Debug, Clone, Copy)]
+pub struct Undo {
+    mv: Move,
+    /// The card sent to the output, for output moves.
+    output: Option<NormalCard>,
+}
+
+impl From<&State> for Position {
+    fn from(state: &State) -> Self {
+        let board = state.board.iter().cloned().collect_vec();
+        Self {
+            stack_keys: board.iter().map(zobrist::stack_key).collect(),
+            board,
+            holes: state.placeholders.holes.iter().copied().collect(),
+            output: state.output,
+            hash: state.zobrist_hash(),
+        }
+    }
+}
+
+impl Layout for Position {
+    fn stacks(&self) -> impl Iterator<Item = &CardStack> {
+        self.board.iter()
+    }
+
+    fn holes(&self) -> impl Iterator<Item = &PlaceHolder> {
+        self.holes.iter()
+    }
+
+    fn output(&self) -> &Output {
+        &self.output
+    }
+}
+
+/// Indices of `items` in sorted order, equal elements keeping their order.
+fn sorted_indices<T: Ord>(items: &[T]) -> Vec<usize> {
+    (0..items.len())
+        .sorted_by_key(|index| &items[*index])
+        .collect()
+}
+
+/// Position of each element of `items` in sorted order, the inverse of [`sorted_indices`].
+fn sorted_positions<T: Ord>(items: &[T]) -> Vec<usize> {
+    let mut positions = vec![0; items.len()];
+    for (sorted, index) in sorted_indices(items).into_iter().enumerate() {
+        positions[index] = sorted;
+    }
+    positions
+}
+
+/// A position seen in normalized order, to generate moves in the same order as for its
+/// normalized state.
+struct NormalizedView<'a> {
+    position: &'a Position,
+    stacks: Vec<usize>,
+    holes: Vec<usize>,
+}
+
+impl Layout for NormalizedView<'_> {
+    fn stacks(&self) -> impl Iterator<Item = &CardStack> {
+        self.stacks.iter().map(|index| &self.position.board[*index])
+    }
+
+    fn holes(&self) -> impl Iterator<Item = &PlaceHolder> {
+        self.holes.iter().map(|index| &self.position.holes[*index])
+    }
+
+    fn output(&self) -> &Output {
+        &self.position.output
+    }
+}
+
+impl NormalizedView<'_> {
+    /// Translates `mv`, generated from this view, back to the positions of the stacks and
+    /// placeholders.
+    fn denormalize(&self, mv: Move) -> Move {
+        mv.map_indices(|index| self.stacks[index], |index| self.holes[index])
+    }
+}
+
+impl Position {
+    /// Converts back into a normalized [`State`].
+    pub fn to_state(&self) -> State {
+        State {
+            board: self.board.iter().cloned().collect(),
+            output: self.output,
+            placeholders: PlaceHolders {
+                holes: self.holes.iter().copied().collect(),
+            },
+        }
+    }
+
+    /// Packs the normalized state into the default form, see [`State::pack`].
+    pub fn pack(&self) -> PackedState {
+        self.pack_as()
+    }
+
+    /// Packs the normalized state into `WORDS` words, see [`State::pack_as`].
+    pub fn pack_as<const WORDS: usize>(&self) -> PackedState<WORDS> {
+        packed::pack(
+            self.board.iter().sorted(),
+            self.holes.iter().sorted(),
+            &self.output,
+        )
+    }
+
+    /// Zobrist hash, equal to [`State::zobrist_hash`] of the normalized state.
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Whether the game is won, see [`rules::is_win`].
+    pub fn is_win(&self) -> bool {
+        rules::is_win(self)
+    }
+
+    fn normalized_view(&self) -> NormalizedView<'_> {
+        NormalizedView {
+            position: self,
+            stacks: sorted_indices(&self.board),
+            holes: sorted_indices(&self.holes),
+        }
+    }
+
+    /// Every legal move from this position, in the order [`State::legal_moves`] generates them
+    /// for the normalized state.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let view = self.normalized_view();
+        rules::legal_moves(&view)
+            .map(|mv| view.denormalize(mv))
+            .collect()
+    }
+
+    /// An output move that can never make the game harder, the same as [`State::safe_output`]
+    /// for the normalized state.
+    pub fn safe_output(&self, game: &GameConfig) -> Option<Move> {
+        let view = self.normalized_view();
+        rules::safe_output(&view, game).map(|mv| view.denormalize(mv))
+    }
+
+    /// Translates `mv`, generated from this position, to the normalized state returned by
+    /// [`Position::to_state`].
+    pub fn normalize_move(&self, mv: &Move) -> Move {
+        let stacks = sorted_positions(&self.board);
+        let holes = sorted_positions(&self.holes);
+        mv.map_indices(|index| stacks[index], |index| holes[index])
+    }
+
+    fn push(&mut self, stack: usize, card: Card) {
+        let key = &mut self.stack_keys[stack];
+        let old_hash = zobrist::stack_hash(*key);
+        *key ^= zobrist::card_key(self.board[stack].cards.len(), &card);
+        self.hash = self
+            .hash
+            .wrapping_sub(old_hash)
+            .wrapping_add(zobrist::stack_hash(*key));
+        self.board[stack].cards.push(card);
+    }
+
+    fn pop(&mut self, stack: usize) -> Card {
+        let card = self.board[stack]
+            .cards
+            .pop()
+            .expect("stack should not be empty");
+        let key = &mut self.stack_keys[stack];
+        let old_hash = zobrist::stack_hash(*key);
+        *key ^= zobrist::card_key(self.board[stack].cards.len(), &card);
+        self.hash = self
+            .hash
+            .wrapping_sub(old_hash)
+            .wrapping_add(zobrist::stack_hash(*key));
+        card
+    }
+
+    fn set_hole(&mut self, slot: usize, content: Option<CardOrBundle>) -> Option<CardOrBundle> {
+        let hole = &mut self.holes[slot];
+        self.hash = self.hash.wrapping_sub(zobrist::placeholder_hash(hole));
+        let old = std::mem::replace(&mut hole.0, content);
+        self.hash = self.hash.wrapping_add(zobrist::placeholder_hash(hole));
+        old
+    }
+
+    fn set_output(&mut self, card: &NormalCard, number: CardNumber) {
+        let output = &mut self.output[&card.color];
+        self.hash = self
+            .hash
+            .wrapping_sub(zobrist::output_hash(card.color, *output))
+            .wrapping_add(zobrist::output_hash(card.color, number));
+        *output = number;
+    }
+
+    fn take(&mut self, source: &CardSource) -> Card {
+        match *source {
+            CardSource::Stack(stack) => self.pop(stack),
+            CardSource::PlaceHolder(slot) => match self.set_hole(slot, None) {
+                Some(CardOrBundle::Card(card)) => card,
+                _ => panic!("slot should hold a card"),
+            },
+        }
+    }
+
+    fn put(&mut self, source: &CardSource, card: Card) {
+        match *source {
+            CardSource::Stack(stack) => self.push(stack, card),
+            CardSource::PlaceHolder(slot) => {
+                let old = self.set_hole(slot, Some(CardOrBundle::Card(card)));
+                assert!(old.is_none(), "placeholder should be empty");
+            }
+        }
+    }
+
+    fn move_cards(&mut self, from: usize, to: usize, count: usize) {
+        let start = self.board[from].cards.len() - count;
+        for index in start..start + count {
+            let card = self.board[from].cards[index];
+            self.push(to, card);
+        }
+        for _ in 0..count {
+            self.pop(from);
+        }
+    }
+
+    /// Plays `mv`, which must be legal in this position, returning what takes it back.
+    pub fn apply(&mut self, mv: &Move) -> Undo {
+        let mut output = None;
+        match *mv {
+            Move::Output { from } => {
+                let Card::Normal(card) = self.take(&from) else {
+                    panic!("only numbered cards can be sent to the output");
+                };
+                assert_eq!(self.output[&card.color].next(), card.number);
+                self.set_output(&card, card.number);
+                output = Some(card);
+            }
+            Move::StackToStack { from, to, count } => self.move_cards(from, to, count),
+            Move::StackToPlaceHolder { from, to } => {
+                let card = self.pop(from);
+                self.put(&CardSource::PlaceHolder(to), card);
+            }
+            Move::PlaceHolderToStack { from, to } => {
+                let card = self.take(&CardSource::PlaceHolder(from));
+                self.push(to, card);
+            }
+            Move::PromoteBeast {
+                beast,
+                pirates,
+                into,
+            } => {
+                let Card::Beast(beast) = self.take(&beast) else {
+                    panic!("should be a beast");
+                };
+                for pirate in &pirates {
+                    assert_eq!(self.take(pirate), Card::Pirate);
+                }
+                let old = self.set_hole(into, Some(CardOrBundle::BeastBundle(beast)));
+                assert!(old.is_none(), "placeholder should be empty");
+            }
+        }
+        Undo { mv: *mv, output }
+    }
+
+    /// Takes back the move returned by the latest [`Position::apply`] not yet taken back.
+    pub fn undo(&mut self, undo: Undo) {
+        match undo.mv {
+            Move::Output { from } => {
+                let card = undo.output.expect("output moves record their card");
+                self.set_output(&card, CardNumber(card.number.0 - 1));
+                self.put(&from, Card::Normal(card));
+            }
+            Move::StackToStack { from, to, count } => self.move_cards(to, from, count),
+            Move::StackToPlaceHolder { from, to } => {
+                let card = self.take(&CardSource::PlaceHolder(to));
+                self.push(from, card);
+            }
+            Move::PlaceHolderToStack { from, to } => {
+                let card = self.pop(to);
+                self.put(&CardSource::PlaceHolder(from), card);
+            }
+            Move::PromoteBeast {
+                beast,
+                pirates,
+                into,
+            } => {
+                let Some(CardOrBundle::BeastBundle(beast_card)) = self.set_hole(into, None) else {
+                    panic!("slot should hold a bundle");
+                };
+                for pirate in pirates.iter().rev() {
+                    self.put(pirate, Card::Pirate);
+                }
+                self.put(&beast, Card::Beast(beast_card));
+            }
+        }
+    }
+}
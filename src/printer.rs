@@ -1,13 +1,19 @@
+//! Colored terminal rendering of a [`DenormalizedState`].
+
 use std::fmt::Display;
 
+use itertools::Itertools;
+
 use crate::{
+    actions::{CardSource, Move},
     ansi,
+    config::GameConfig,
     denormalized::DenormalizedState,
     state::{Beast, Card, CardColor, CardOrBundle, NormalCard},
 };
 
 fn u8_to_digit(num: u8) -> char {
-    num.to_string().chars().into_iter().next().unwrap()
+    num.to_string().chars().next().unwrap()
 }
 
 impl Card {
@@ -30,6 +36,7 @@ impl Card {
                     CardColor::Red => ansi::Color::Red,
                     CardColor::Teal => ansi::Color::Cyan,
                     CardColor::Blue => ansi::Color::Blue,
+                    CardColor::Green => ansi::Color::Green,
                 },
                 [
                     u8_to_digit(normal_card.number.0),
@@ -37,6 +44,7 @@ impl Card {
                         CardColor::Red => 'r',
                         CardColor::Teal => 't',
                         CardColor::Blue => 'b',
+                        CardColor::Green => 'g',
                     },
                 ],
             ),
@@ -44,9 +52,55 @@ impl Card {
     }
 }
 
+impl Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_color, chars) = self.as_chars();
+        f.write_str(String::from_iter(chars).trim_start())
+    }
+}
+
+/// Colors of the output, from left to right.
+pub(crate) const OUTPUT_COLORS: [CardColor; 4] = [
+    CardColor::Blue,
+    CardColor::Red,
+    CardColor::Teal,
+    CardColor::Green,
+];
+
+/// The whole table of a variant: slots and output on the top row, columns below.
+///
+/// Created with [`DenormalizedState::display_with`].
+pub struct Table<'a> {
+    state: &'a DenormalizedState,
+    config: &'a GameConfig,
+}
+
+impl DenormalizedState {
+    /// Displays `self` as a state of the variant described by `config`, with an output for each
+    /// of its colors. The [`Display`] implementation of the state shows the standard game.
+    pub fn display_with<'a>(&'a self, config: &'a GameConfig) -> Table<'a> {
+        Table {
+            state: self,
+            config,
+        }
+    }
+}
+
 impl Display for DenormalizedState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let max_stack_height = self
+        self.display_with(&GameConfig::STANDARD).fmt(f)
+    }
+}
+
+impl Display for Table<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Table { state, config } = self;
+        let colors = OUTPUT_COLORS
+            .into_iter()
+            .filter(|color| config.card_colors().contains(color))
+            .collect_vec();
+        let slots = state.placeholders.holes.len();
+        let max_stack_height = state
             .board
             .iter()
             .map(|stack| stack.cards.len())
@@ -59,10 +113,10 @@ impl Display for DenormalizedState {
 
         let mut table = ansi::Matrix::with_size(
             max_stack_height * 2 + separation + 1 + 1,
-            (card_width + 1) * 6 + 1,
+            (card_width + 1) * state.board.len().max(slots + colors.len()) + 1,
         );
 
-        for (x, placeholder) in self.placeholders.holes.iter().enumerate() {
+        for (x, placeholder) in state.placeholders.holes.iter().enumerate() {
             if let Some(card) = placeholder.0 {
                 let (color, chars) = match card {
                     CardOrBundle::Card(card) => card.as_chars(),
@@ -73,7 +127,7 @@ impl Display for DenormalizedState {
             }
         }
 
-        for (x, stack) in self.board.iter().enumerate() {
+        for (x, stack) in state.board.iter().enumerate() {
             for (y, card) in stack.cards.iter().enumerate() {
                 let start = x * (card_width + 1) + 1;
                 let y = y * 2 + separation + 1 + 1;
@@ -82,14 +136,13 @@ impl Display for DenormalizedState {
             }
         }
 
-        let colors = [CardColor::Blue, CardColor::Red, CardColor::Teal];
         for (x, (color, highest_number)) in colors
             .into_iter()
-            .map(|color| (color, self.output[&color]))
+            .map(|color| (color, state.output[&color]))
             .enumerate()
         {
             if highest_number.0 > 0 {
-                let start = (x + 3) * (card_width + 1) + 1;
+                let start = (x + slots) * (card_width + 1) + 1;
 
                 let (color, chars) = Card::Normal(NormalCard {
                     color,
@@ -103,3 +156,101 @@ impl Display for DenormalizedState {
         table.fmt(f)
     }
 }
+
+/// One line description of an on-screen move, such as "move 3r (+2) from column 4 to column 1".
+///
+/// Created with [`DenormalizedState::describe`].
+pub struct MoveDescription<'a> {
+    state: &'a DenormalizedState,
+    mv: Move,
+}
+
+impl DenormalizedState {
+    /// Describes `mv`, whose indices refer to the columns and slots of `self` as returned by
+    /// [`DenormalizedState::translate`]. Columns and slots are numbered from 1.
+    pub fn describe(&self, mv: Move) -> MoveDescription<'_> {
+        MoveDescription { state: self, mv }
+    }
+
+    fn card_at(&self, source: &CardSource) -> Card {
+        match *source {
+            CardSource::Stack(column) => *self.board[column]
+                .cards
+                .last()
+                .expect("stack should not be empty"),
+            CardSource::PlaceHolder(slot) => match self.placeholders.holes[slot].0 {
+                Some(CardOrBundle::Card(card)) => card,
+                _ => panic!("slot should hold a card"),
+            },
+        }
+    }
+}
+
+struct SourceName(CardSource);
+
+impl Display for SourceName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            CardSource::Stack(column) => write!(f, "column {}", column + 1),
+            CardSource::PlaceHolder(slot) => write!(f, "slot {}", slot + 1),
+        }
+    }
+}
+
+impl Display for MoveDescription<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state;
+        match self.mv {
+            Move::Output { from } => write!(
+                f,
+                "send {} from {} to the output",
+                state.card_at(&from),
+                SourceName(from)
+            ),
+            Move::StackToStack { from, to, count } => {
+                let cards = &state.board[from].cards;
+                write!(f, "move {}", cards[cards.len() - count])?;
+                if count > 1 {
+                    write!(f, " (+{})", count - 1)?;
+                }
+                write!(
+                    f,
+                    " from {} to {}",
+                    SourceName(CardSource::Stack(from)),
+                    SourceName(CardSource::Stack(to))
+                )
+            }
+            Move::StackToPlaceHolder { from, to } => write!(
+                f,
+                "move {} from {} to {}",
+                state.card_at(&CardSource::Stack(from)),
+                SourceName(CardSource::Stack(from)),
+                SourceName(CardSource::PlaceHolder(to))
+            ),
+            Move::PlaceHolderToStack { from, to } => write!(
+                f,
+                "move {} from {} to {}",
+                state.card_at(&CardSource::PlaceHolder(from)),
+                SourceName(CardSource::PlaceHolder(from)),
+                SourceName(CardSource::Stack(to))
+            ),
+            Move::PromoteBeast {
+                beast,
+                pirates: [pirate1, pirate2],
+                into: _,
+            } => {
+                let beast_source = beast;
+                let Card::Beast(beast) = state.card_at(&beast) else {
+                    panic!("should be a beast");
+                };
+                write!(
+                    f,
+                    "promote {beast} from {} with pirates from {} and {}",
+                    SourceName(beast_source),
+                    SourceName(pirate1),
+                    SourceName(pirate2)
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Machine-readable solver results.
+//!
+//! A [`SolveReport`] serializes to JSON as
+//!
+//! ```json
+//! {
+//!   "initial": { "placeholders": { "holes": [null, null, null] },
+//!                "output": { "by_color": [0, 0, 0] },
+//!                "board": [{ "cards": ["pirate", { "normal": { "number": 3, "color": "red" } }] }] },
+//!   "solution": { "states": [...], "moves": [{ "stack_to_place_holder": { "from": 0, "to": 2 } }] },
+//!   "partial": null,
+//!   "summary": { "outcome": "solved", "limit": null, "solved": true, "length": 48,
+//!                "expanded_nodes": 1234, "reachable_states": null, "time_seconds": 0.5 }
+//! }
+//! ```
+//!
+//! Cards are `"pirate"`, `{ "beast": "whale" | "tentacle" | "ship" }` or
+//! `{ "normal": { "number": 1..=9, "color": "red" | "teal" | "blue" } }`. A placeholder is `null`
+//! when empty, `{ "card": <card> }` or `{ "beast_bundle": <beast> }`. The output holds the highest
+//! number sent out for red, teal and blue, in that order. Stacks list their cards from the bottom
+//! to the exposed one.
+//!
+//! Moves refer to on-screen columns and slots numbered from 0, and `moves[i]` leads from
+//! `states[i]` to `states[i + 1]`. See [`Move`](crate::actions::Move) for every move kind.
+//! `outcome` is `"solved"`, `"unsolvable"` when every reachable state was explored, in which case
+//! `reachable_states` counts them unless the strategy does not keep them, or
+//! `"budget_exceeded"` with `limit` set to `"time"`, `"expanded_nodes"` or `"memory"`. `solution`
+//! and `length` are only set when solved, and `partial` holds the line to the state closest to a
+//! win, as measured by the heuristic, when the budget was exceeded.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    denormalized::{DenormalizedSolution, DenormalizedState},
+    solver::{Limit, Outcome, SolveResult},
+};
+
+/// Everything known about solving a single deal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolveReport {
+    pub initial: DenormalizedState,
+    pub solution: Option<DenormalizedSolution>,
+    /// Best line found before running out of budget.
+    pub partial: Option<DenormalizedSolution>,
+    pub summary: Summary,
+}
+
+/// How the search ended, see [`Outcome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeKind {
+    Solved,
+    Unsolvable,
+    BudgetExceeded,
+}
+
+/// Solution metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub outcome: OutcomeKind,
+    /// The budget limit that stopped the search, if any.
+    pub limit: Option<Limit>,
+    pub solved: bool,
+    /// Number of moves of the solution.
+    pub length: Option<usize>,
+    /// Number of states whose successors were generated during the search.
+    pub expanded_nodes: usize,
+    /// Number of distinct states reachable from the start, when proven unsolvable.
+    pub reachable_states: Option<usize>,
+    /// Wall-clock time spent searching.
+    pub time_seconds: f64,
+}
+
+impl SolveReport {
+    pub fn new(initial: DenormalizedState, result: &SolveResult) -> Self {
+        let (outcome, limit, solution, partial) = match &result.outcome {
+            Outcome::Solved(solution) => (OutcomeKind::Solved, None, Some(solution), None),
+            Outcome::Unsolvable { .. } => (OutcomeKind::Unsolvable, None, None, None),
+            Outcome::BudgetExceeded { limit, partial } => (
+                OutcomeKind::BudgetExceeded,
+                Some(*limit),
+                None,
+                Some(partial),
+            ),
+        };
+
+        Self {
+            summary: Summary {
+                outcome,
+                limit,
+                solved: solution.is_some(),
+                length: solution.map(|solution| solution.moves.len()),
+                expanded_nodes: result.statistics.expanded_nodes,
+                reachable_states: match result.outcome {
+                    Outcome::Unsolvable { reachable_states } => reachable_states,
+                    _ => None,
+                },
+                time_seconds: result.statistics.elapsed.as_secs_f64(),
+            },
+            solution: solution.map(|solution| initial.replay(solution)),
+            partial: partial.map(|partial| initial.replay(partial)),
+            initial,
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Rules of Pirate Solitaire, defining every legal move and when the game is won.
+//!
+//! The table holds six columns of cards, three slots and the output, which keeps the highest
+//! number sent out for each of the three colors. The deck has 27 numbered cards, 1 to 9 in red,
+//! teal and blue, 6 pirates and 3 beasts: a whale, a tentacle and a ship. A card is exposed when
+//! it is the top card of a column or sits alone in a slot. Variants change the number of columns,
+//! slots, numbers, colors and pirates, see [`GameConfig`], but follow the same rules.
+//!
+//! 1. **Output**: an exposed numbered card can be sent to the output when it is the next number
+//!    of its color, starting from 1.
+//! 2. **Stacking**: a card can be put on an empty column, or on a numbered card one higher and of
+//!    another color. Pirates and beasts only ever go on an empty column.
+//! 3. **Runs**: the top cards of a column can be moved together onto another column when each one
+//!    stacks on the card below it, the bottom one of the run following rule 2.
+//! 4. **Holding**: the exposed card of a column can be put into an empty slot, and a card held in
+//!    a slot can be put back onto a column following rule 2. Cards never move from slot to slot.
+//! 5. **Promotion**: an exposed beast and any two exposed pirates are bundled together into a
+//!    slot: the slot the beast leaves if it was held in one, otherwise the first empty slot,
+//!    otherwise the first slot left by one of the pirates. A bundle stays in its slot for the rest
+//!    of the game.
+//! 6. **Win**: the game is won once every numbered card is out and every beast is bundled, that
+//!    is when the columns are empty and no slot holds a loose card.
+//!
+//! [`legal_moves`] generates the moves allowed by rules 1 to 5 and [`is_win`] checks rule 6.
+
+use itertools::{Either, Itertools};
+
+use crate::{
+    actions::{CardSource, Move},
+    config::GameConfig,
+    state::{Card, CardOrBundle, CardStack, Layout, NormalCard, Output},
+};
+
+enum CardSplitOrigin<'a> {
+    Stack { index: usize, cards: &'a [Card] },
+    PlaceHolder { index: usize, card: &'a Card },
+}
+
+impl Output {
+    pub(crate) fn can_output_card(&self, NormalCard { number, color }: &NormalCard) -> bool {
+        &self[color].next() == number
+    }
+
+    /// Whether sending `card` out now can never make the game harder. A card only ever serves as
+    /// a base for the next lower cards of the other colors: 1s can go out as soon as they are
+    /// exposed, so they never need one, and neither do cards already sent out.
+    fn is_safe_to_output(
+        &self,
+        NormalCard { number, color }: &NormalCard,
+        game: &GameConfig,
+    ) -> bool {
+        number.0 <= 2
+            || game
+                .card_colors()
+                .filter(|other| other != color)
+                .all(|other| self[&other].0 + 1 >= number.0)
+    }
+}
+
+impl Card {
+    pub(crate) fn can_stack_with(&self, card_to_place: &Card) -> bool {
+        self.normal()
+            .zip(card_to_place.normal())
+            .is_some_and(|(card, card_to_place)| {
+                card.number == card_to_place.number.next() && card.color != card_to_place.color
+            })
+    }
+}
+
+impl CardStack {
+    fn can_stack_with(&self, card_to_place: &Card) -> bool {
+        self.cards
+            .last()
+            .is_none_or(|card| card.can_stack_with(card_to_place))
+    }
+}
+
+fn valid_card_outputs(layout: &impl Layout) -> impl Iterator<Item = CardSource> + '_ {
+    layout
+        .stacks()
+        .enumerate()
+        .filter_map(move |(index, stack)| {
+            stack
+                .cards
+                .last()
+                .and_then(|card| card.normal())
+                .is_some_and(|card| layout.output().can_output_card(card))
+                .then_some(CardSource::Stack(index))
+        })
+        .chain(layout.holes().enumerate().filter_map(|(index, hole)| {
+            if let Some(CardOrBundle::Card(Card::Normal(ref card))) = hole.0
+                && layout.output().can_output_card(card)
+            {
+                Some(CardSource::PlaceHolder(index))
+            } else {
+                None
+            }
+        }))
+}
+
+fn valid_card_splits(layout: &impl Layout) -> impl Iterator<Item = CardSplitOrigin<'_>> {
+    layout
+        .stacks()
+        .enumerate()
+        .flat_map(|(index, stack)| {
+            let max_split_size = match stack.cards.len() {
+                0 => 0,
+                _ => {
+                    stack
+                        .cards
+                        .iter()
+                        .rev()
+                        .skip(1)
+                        .zip(stack.cards.iter().rev())
+                        .take_while(|(prev, top)| prev.can_stack_with(top))
+                        .count()
+                        + 1
+                }
+            };
+            (1..=max_split_size).map(move |split_size| CardSplitOrigin::Stack {
+                index,
+                cards: &stack.cards[stack.cards.len() - split_size..],
+            })
+        })
+        .chain(layout.holes().enumerate().flat_map(|(index, hole)| {
+            hole.0.as_ref().and_then(|card_or_bundle| {
+                if let CardOrBundle::Card(card) = card_or_bundle {
+                    Some(CardSplitOrigin::PlaceHolder { index, card })
+                } else {
+                    None
+                }
+            })
+        }))
+}
+
+fn exposed_beasts(layout: &impl Layout) -> impl Iterator<Item = CardSource> + '_ {
+    layout
+        .stacks()
+        .enumerate()
+        .flat_map(|(index, stack)| {
+            stack
+                .cards
+                .last()
+                .is_some_and(|card| matches!(card, Card::Beast(_)))
+                .then_some(CardSource::Stack(index))
+        })
+        .chain(layout.holes().enumerate().flat_map(|(index, hole)| {
+            hole.0
+                .as_ref()
+                .is_some_and(|card| matches!(card, CardOrBundle::Card(Card::Beast(_))))
+                .then_some(CardSource::PlaceHolder(index))
+        }))
+}
+
+fn exposed_pirates(layout: &impl Layout) -> impl Iterator<Item = CardSource> + '_ {
+    layout
+        .stacks()
+        .enumerate()
+        .flat_map(|(index, stack)| {
+            stack
+                .cards
+                .last()
+                .is_some_and(|card| matches!(card, Card::Pirate))
+                .then_some(CardSource::Stack(index))
+        })
+        .chain(layout.holes().enumerate().flat_map(|(index, hole)| {
+            hole.0
+                .as_ref()
+                .is_some_and(|card| matches!(card, CardOrBundle::Card(Card::Pirate)))
+                .then_some(CardSource::PlaceHolder(index))
+        }))
+}
+
+/// Slot a beast taken from `beast` is bundled into with the pirates taken from `pirates`, if any,
+/// following rule 5.
+fn promotion_slot(
+    layout: &impl Layout,
+    beast: CardSource,
+    pirates: [CardSource; 2],
+) -> Option<usize> {
+    if let CardSource::PlaceHolder(slot) = beast {
+        return Some(slot);
+    }
+    layout
+        .holes()
+        .position(|hole| hole.0.is_none())
+        .or_else(|| {
+            pirates
+                .into_iter()
+                .filter_map(|pirate| match pirate {
+                    CardSource::PlaceHolder(slot) => Some(slot),
+                    CardSource::Stack(_) => None,
+                })
+                .min()
+        })
+}
+
+/// Every legal move from `layout`, referring to its stacks and placeholders by their position in
+/// its iteration order.
+pub fn legal_moves(layout: &impl Layout) -> impl Iterator<Item = Move> + '_ {
+    let card_output_moves = valid_card_outputs(layout).map(|from| Move::Output { from });
+
+    let card_split_moves =
+        valid_card_splits(layout).flat_map(move |split_origin| match split_origin {
+            CardSplitOrigin::PlaceHolder { index: from, card } => {
+                Either::Left(layout.stacks().enumerate().filter_map(move |(to, stack)| {
+                    stack
+                        .can_stack_with(card)
+                        .then_some(Move::PlaceHolderToStack { from, to })
+                }))
+            }
+            CardSplitOrigin::Stack { index: from, cards } => {
+                Either::Right(layout.stacks().enumerate().filter_map(move |(to, stack)| {
+                    stack
+                        .can_stack_with(cards.first().unwrap())
+                        .then_some(Move::StackToStack {
+                            from,
+                            to,
+                            count: cards.len(),
+                        })
+                }))
+            }
+        });
+
+    let card_hold_moves = layout
+        .stacks()
+        .enumerate()
+        .filter(|(_, stack)| !stack.cards.is_empty())
+        .flat_map(|(from, _)| {
+            layout
+                .holes()
+                .enumerate()
+                .filter(|(_, hole)| hole.0.is_none())
+                .map(move |(to, _)| Move::StackToPlaceHolder { from, to })
+        });
+
+    let beast_promotion_moves = exposed_beasts(layout)
+        .cartesian_product(
+            exposed_pirates(layout)
+                .collect_vec()
+                .into_iter()
+                .tuple_combinations(),
+        )
+        .filter_map(move |(beast, (pirate1, pirate2))| {
+            let pirates = [pirate1, pirate2];
+            promotion_slot(layout, beast, pirates).map(|into| Move::PromoteBeast {
+                beast,
+                pirates,
+                into,
+            })
+        });
+
+    card_output_moves
+        .chain(card_split_moves)
+        .chain(card_hold_moves)
+        .chain(beast_promotion_moves)
+}
+
+/// An output move from `layout`, a state of the variant described by `game`, that can never make
+/// the game harder, if any, so it can be played without considering the alternatives.
+pub fn safe_output(layout: &impl Layout, game: &GameConfig) -> Option<Move> {
+    valid_card_outputs(layout)
+        .find(|from| {
+            let card = match *from {
+                CardSource::Stack(index) => layout
+                    .stacks()
+                    .nth(index)
+                    .and_then(|stack| stack.cards.last()),
+                CardSource::PlaceHolder(index) => {
+                    match layout.holes().nth(index).and_then(|hole| hole.0.as_ref()) {
+                        Some(CardOrBundle::Card(card)) => Some(card),
+                        _ => None,
+                    }
+                }
+            };
+            card.and_then(Card::normal)
+                .is_some_and(|card| layout.output().is_safe_to_output(card, game))
+        })
+        .map(|from| Move::Output { from })
+}
+
+/// Whether `layout` is won: every column is empty and no slot holds a loose card.
+pub fn is_win(layout: &impl Layout) -> bool {
+    layout.stacks().all(|stack| stack.cards.is_empty())
+        && layout
+            .holes()
+            .all(|hole| !matches!(hole.0, Some(CardOrBundle::Card(_))))
+}
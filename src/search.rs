@@ -0,0 +1,609 @@
+//! Search algorithms behind [`solve_with`](crate::solver::solve_with).
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    hash::{BuildHasherDefault, Hash, Hasher},
+    time::Instant,
+};
+
+use indexmap::{IndexMap, IndexSet, map::Entry};
+use rustc_hash::FxBuildHasher;
+
+use crate::{
+    actions::Move,
+    config::GameConfig,
+    packed::{self, COMPACT_WORDS, PackedState, WIDE_WORDS},
+    position::{Position, Undo},
+    solver::{AutoPlay, Budget, Heuristic, Limit, Solution},
+    state::{Card, Layout, State},
+};
+
+/// How a search ended, before being wrapped into an [`Outcome`](crate::solver::Outcome).
+pub(crate) enum SearchEnd {
+    Solved(Solution),
+    /// No winning state is reachable. Holds the number of reachable states when the algorithm
+    /// keeps them.
+    Exhausted(Option<usize>),
+    BudgetExceeded {
+        limit: Limit,
+        partial: Solution,
+    },
+}
+
+impl SearchEnd {
+    /// Prepends `opening`, ending at the state the search started from, to the lines found.
+    pub(crate) fn after(self, opening: Solution) -> Self {
+        let prepend = |line: Solution| {
+            let mut states = opening.states.clone();
+            states.extend(line.states.into_iter().skip(1));
+            let mut moves = opening.moves.clone();
+            moves.extend(line.moves);
+            Solution { states, moves }
+        };
+        match self {
+            SearchEnd::Solved(solution) => SearchEnd::Solved(prepend(solution)),
+            SearchEnd::Exhausted(reachable) => SearchEnd::Exhausted(reachable),
+            SearchEnd::BudgetExceeded { limit, partial } => SearchEnd::BudgetExceeded {
+                limit,
+                partial: prepend(partial),
+            },
+        }
+    }
+}
+
+impl Solution {
+    /// Plays `mv` from the last state of the line.
+    fn play(&mut self, mv: Move) {
+        let mut state = self.states.last().unwrap().clone();
+        state.apply(&mv);
+        self.states.push(state);
+        self.moves.push(mv);
+    }
+
+    /// Plays the automatic moves from the last state of the line.
+    fn auto_play(&mut self, tracker: &Tracker) {
+        let mut state = self.states.last().unwrap().clone();
+        for mv in tracker.auto_play.play(&mut state, tracker.game) {
+            self.play(mv);
+        }
+    }
+}
+
+/// A searched move, followed by the automatic moves it triggers.
+struct Successor {
+    mv: Move,
+    state: State,
+    /// Number of moves played, the searched one included.
+    moves: usize,
+}
+
+/// Bookkeeping shared by every algorithm: budget checks, counters and the best line so far.
+pub(crate) struct Tracker<'a> {
+    budget: &'a Budget,
+    heuristic: Heuristic,
+    game: &'a GameConfig,
+    auto_play: AutoPlay,
+    /// Asked before every expansion whether another search made this one useless.
+    cancelled: Option<&'a dyn Fn() -> bool>,
+    start_time: Instant,
+    pub(crate) expanded_nodes: usize,
+    stored_bytes: usize,
+    best_estimate: usize,
+}
+
+impl<'a> Tracker<'a> {
+    pub(crate) fn new(
+        budget: &'a Budget,
+        game: &'a GameConfig,
+        heuristic: Heuristic,
+        auto_play: AutoPlay,
+    ) -> Self {
+        Self {
+            budget,
+            game,
+            heuristic,
+            auto_play,
+            cancelled: None,
+            start_time: Instant::now(),
+            expanded_nodes: 0,
+            stored_bytes: 0,
+            best_estimate: usize::MAX,
+        }
+    }
+
+    /// Stops the search as soon as `cancelled` returns true, as if out of time.
+    pub(crate) fn cancelled_by(self, cancelled: &'a dyn Fn() -> bool) -> Self {
+        Self {
+            cancelled: Some(cancelled),
+            ..self
+        }
+    }
+
+    fn exceeded_limit(&self) -> Option<Limit> {
+        if self.cancelled.is_some_and(|cancelled| cancelled()) {
+            Some(Limit::Time)
+        } else if self
+            .budget
+            .expanded_nodes
+            .is_some_and(|limit| self.expanded_nodes >= limit)
+        {
+            Some(Limit::ExpandedNodes)
+        } else if self
+            .budget
+            .memory
+            .is_some_and(|limit| self.stored_bytes >= limit)
+        {
+            Some(Limit::Memory)
+        } else if self
+            .budget
+            .time
+            .is_some_and(|limit| self.start_time.elapsed() >= limit)
+        {
+            Some(Limit::Time)
+        } else {
+            None
+        }
+    }
+
+    /// The automatic moves played from `start` before searching.
+    pub(crate) fn opening(&self, start: &State) -> Solution {
+        let mut opening = Solution {
+            states: vec![start.clone()],
+            moves: vec![],
+        };
+        opening.auto_play(self);
+        opening
+    }
+
+    fn successors(&self, state: &State) -> Vec<Successor> {
+        state
+            .get_next_states()
+            .map(|(mv, mut state)| {
+                let moves = 1 + self.auto_play.play(&mut state, self.game).len();
+                Successor { mv, state, moves }
+            })
+            .collect()
+    }
+
+    fn store(&mut self, bytes: usize) {
+        self.stored_bytes += bytes;
+    }
+
+    /// Records the expansion of `state`, returning whether it is the closest to a win so far.
+    fn expand(&mut self, state: &impl Layout) -> bool {
+        self.expanded_nodes += 1;
+        let estimate = self.heuristic.estimate(state);
+        let is_best = estimate < self.best_estimate;
+        if is_best {
+            self.best_estimate = estimate;
+        }
+        is_best
+    }
+}
+
+impl State {
+    /// Rough number of heap and inline bytes taken by this state.
+    pub fn approximate_size(&self) -> usize {
+        // a B-tree entry costs roughly its key, its count and a share of the node bookkeeping
+        const ENTRY_OVERHEAD: usize = 3 * size_of::<usize>();
+
+        let board = self
+            .board
+            .iter()
+            .map(|stack| {
+                size_of_val(stack) + ENTRY_OVERHEAD + stack.cards.capacity() * size_of::<Card>()
+            })
+            .sum::<usize>();
+        let placeholders = self
+            .placeholders
+            .holes
+            .iter()
+            .map(|hole| size_of_val(hole) + ENTRY_OVERHEAD)
+            .sum::<usize>();
+
+        size_of::<Self>() + board + placeholders
+    }
+}
+
+type Nodes<const WORDS: usize> = IndexMap<PackedState<WORDS>, Node, FxBuildHasher>;
+
+/// Bytes taken by a stored state, not counting the spare capacity of the map.
+const fn stored_node_size<const WORDS: usize>() -> usize {
+    size_of::<PackedState<WORDS>>() + size_of::<Node>() + size_of::<usize>()
+}
+
+/// A stored state: how it was first reached, with the fewest moves found so far.
+struct Node {
+    parent: usize,
+    mv: Option<Move>,
+    moves: usize,
+}
+
+fn line_to<const WORDS: usize>(
+    nodes: &Nodes<WORDS>,
+    mut index: usize,
+    tracker: &Tracker,
+) -> Solution {
+    let mut searched_moves = vec![];
+    while let Some(mv) = nodes[index].mv {
+        searched_moves.push(mv);
+        index = nodes[index].parent;
+    }
+
+    let (start, _) = nodes.get_index(index).unwrap();
+    let mut line = Solution {
+        states: vec![start.unpack()],
+        moves: vec![],
+    };
+    for mv in searched_moves.into_iter().rev() {
+        line.play(mv);
+        line.auto_play(tracker);
+    }
+    line
+}
+
+#[derive(PartialEq, Eq)]
+struct OpenEntry {
+    priority: usize,
+    moves: usize,
+    index: usize,
+}
+
+impl Ord for OpenEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // lowest priority first, deepest first on ties
+        Reverse(self.priority)
+            .cmp(&Reverse(other.priority))
+            .then(self.moves.cmp(&other.moves))
+    }
+}
+
+impl PartialOrd for OpenEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Best-first search expanding states by increasing `moves * move_weight + estimate *
+/// estimate_weight`, which covers A*, weighted A* and greedy best-first search.
+///
+/// States are stored in the smallest [`PackedState`] holding every state of the game.
+pub(crate) fn best_first(
+    start: &State,
+    tracker: &mut Tracker<'_>,
+    move_weight: usize,
+    estimate_weight: usize,
+) -> SearchEnd {
+    if packed::fits::<COMPACT_WORDS>(tracker.game) {
+        best_first_packed::<COMPACT_WORDS>(start, tracker, move_weight, estimate_weight)
+    } else {
+        best_first_packed::<WIDE_WORDS>(start, tracker, move_weight, estimate_weight)
+    }
+}
+
+fn best_first_packed<const WORDS: usize>(
+    start: &State,
+    tracker: &mut Tracker<'_>,
+    move_weight: usize,
+    estimate_weight: usize,
+) -> SearchEnd {
+    let heuristic = tracker.heuristic;
+    let priority = |moves: usize, state: &State| {
+        moves * move_weight + heuristic.estimate(state) * estimate_weight
+    };
+
+    let mut nodes = Nodes::<WORDS>::default();
+    let mut open = BinaryHeap::new();
+    let mut best = 0;
+
+    tracker.store(stored_node_size::<WORDS>());
+    nodes.insert(
+        start.pack_as(),
+        Node {
+            parent: usize::MAX,
+            mv: None,
+            moves: 0,
+        },
+    );
+    open.push(OpenEntry {
+        priority: priority(0, start),
+        moves: 0,
+        index: 0,
+    });
+
+    while let Some(OpenEntry { moves, index, .. }) = open.pop() {
+        let (state, node) = nodes.get_index(index).unwrap();
+        if moves > node.moves {
+            // a shorter way to this state was found after this entry was queued
+            continue;
+        }
+        let state = state.unpack();
+        if state.is_win() {
+            return SearchEnd::Solved(line_to(&nodes, index, tracker));
+        }
+        if let Some(limit) = tracker.exceeded_limit() {
+            return SearchEnd::BudgetExceeded {
+                limit,
+                partial: line_to(&nodes, best, tracker),
+            };
+        }
+        if tracker.expand(&state) {
+            best = index;
+        }
+
+        for Successor {
+            mv,
+            state: successor,
+            moves: played,
+        } in tracker.successors(&state)
+        {
+            let successor_moves = moves + played;
+            let successor_priority = priority(successor_moves, &successor);
+            let successor_index = match nodes.entry(successor.pack_as()) {
+                Entry::Vacant(entry) => {
+                    tracker.store(stored_node_size::<WORDS>());
+                    let successor_index = entry.index();
+                    entry.insert(Node {
+                        parent: index,
+                        mv: Some(mv),
+                        moves: successor_moves,
+                    });
+                    successor_index
+                }
+                Entry::Occupied(mut entry) => {
+                    if entry.get().moves <= successor_moves {
+                        continue;
+                    }
+                    entry.insert(Node {
+                        parent: index,
+                        mv: Some(mv),
+                        moves: successor_moves,
+                    });
+                    entry.index()
+                }
+            };
+            open.push(OpenEntry {
+                priority: successor_priority,
+                moves: successor_moves,
+                index: successor_index,
+            });
+        }
+    }
+
+    SearchEnd::Exhausted(Some(nodes.len()))
+}
+
+/// Key of a visited state, hashed by the Zobrist hash of its [`Position`].
+#[derive(PartialEq, Eq)]
+struct VisitedKey<const WORDS: usize> {
+    hash: u64,
+    state: PackedState<WORDS>,
+}
+
+impl<const WORDS: usize> Hash for VisitedKey<WORDS> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+/// Hands Zobrist hashes over to the hash table unchanged, as they are already well mixed.
+#[derive(Default)]
+struct ZobristHasher(u64);
+
+impl Hasher for ZobristHasher {
+    fn write(&mut self, _: &[u8]) {
+        unreachable!("only Zobrist hashes are hashed");
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.0 = hash;
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+type Visited<const WORDS: usize> = IndexSet<VisitedKey<WORDS>, BuildHasherDefault<ZobristHasher>>;
+
+/// Bytes taken by a visited state, not counting the spare capacity of the set.
+const fn visited_key_size<const WORDS: usize>() -> usize {
+    size_of::<VisitedKey<WORDS>>() + size_of::<usize>()
+}
+
+/// Replays `path`, a line of moves generated from the [`Position`] made from `start`.
+fn replay_path(start: &State, path: &[Move]) -> Solution {
+    let mut position = Position::from(start);
+    let mut line = Solution {
+        states: vec![start.clone()],
+        moves: vec![],
+    };
+    for mv in path {
+        line.moves.push(position.normalize_move(mv));
+        position.apply(mv);
+        line.states.push(position.to_state());
+    }
+    line
+}
+
+/// Moves left to try from a state of the current line.
+struct Frame {
+    /// In reverse order, the next one last.
+    moves: Vec<Move>,
+    /// Takes back the moves leading to this state, in the order they were played.
+    undos: Vec<Undo>,
+}
+
+/// Depth-first search trying moves in generation order, never revisiting a state.
+///
+/// Moves are played and taken back on a single [`Position`], so only the visited states are
+/// stored, in the smallest [`PackedState`] holding every state of the game. When no win is
+/// reachable, every visited state is unpacked into `reachable` if given.
+pub(crate) fn depth_first(
+    start: &State,
+    tracker: &mut Tracker<'_>,
+    reachable: Option<&mut Vec<State>>,
+) -> SearchEnd {
+    if packed::fits::<COMPACT_WORDS>(tracker.game) {
+        depth_first_packed::<COMPACT_WORDS>(start, tracker, reachable)
+    } else {
+        depth_first_packed::<WIDE_WORDS>(start, tracker, reachable)
+    }
+}
+
+fn depth_first_packed<const WORDS: usize>(
+    start: &State,
+    tracker: &mut Tracker<'_>,
+    reachable: Option<&mut Vec<State>>,
+) -> SearchEnd {
+    let mut position = Position::from(start);
+    let mut visited = Visited::<WORDS>::default();
+    let mut path = vec![];
+    let mut best = vec![];
+    let mut frames: Vec<Frame> = vec![];
+    let mut undos = vec![];
+
+    loop {
+        let key = VisitedKey {
+            hash: position.zobrist_hash(),
+            state: position.pack_as(),
+        };
+        if visited.insert(key) {
+            tracker.store(visited_key_size::<WORDS>());
+            if position.is_win() {
+                return SearchEnd::Solved(replay_path(start, &path));
+            }
+            if let Some(limit) = tracker.exceeded_limit() {
+                return SearchEnd::BudgetExceeded {
+                    limit,
+                    partial: replay_path(start, &best),
+                };
+            }
+            if tracker.expand(&position) {
+                best.clone_from(&path);
+            }
+            let mut moves = position.legal_moves();
+            moves.reverse();
+            frames.push(Frame {
+                moves,
+                undos: std::mem::take(&mut undos),
+            });
+        } else {
+            for undo in undos.drain(..).rev() {
+                position.undo(undo);
+                path.pop();
+            }
+        }
+
+        // backtrack to the deepest state with moves left to try
+        let mv = loop {
+            let Some(frame) = frames.last_mut() else {
+                if let Some(reachable) = reachable {
+                    reachable.extend(visited.iter().map(|key| key.state.unpack()));
+                }
+                return SearchEnd::Exhausted(Some(visited.len()));
+            };
+            match frame.moves.pop() {
+                Some(mv) => break mv,
+                None => {
+                    for undo in frames.pop().unwrap().undos.into_iter().rev() {
+                        position.undo(undo);
+                        path.pop();
+                    }
+                }
+            }
+        };
+
+        undos.push(position.apply(&mv));
+        path.push(mv);
+        if let AutoPlay::Safe = tracker.auto_play {
+            while let Some(mv) = position.safe_output(tracker.game) {
+                undos.push(position.apply(&mv));
+                path.push(mv);
+            }
+        }
+    }
+}
+
+enum IdaStep {
+    Found,
+    /// Smallest estimated total beyond the bound, if any state was cut off.
+    Cutoff(Option<usize>),
+    BudgetExceeded(Limit),
+}
+
+struct IdaStar<'t, 'b> {
+    tracker: &'t mut Tracker<'b>,
+    line: Solution,
+    best: Option<Solution>,
+}
+
+impl IdaStar<'_, '_> {
+    fn search(&mut self, bound: usize) -> IdaStep {
+        let state = self.line.states.last().unwrap();
+        let estimate = self.line.moves.len() + self.tracker.heuristic.estimate(state);
+        if estimate > bound {
+            return IdaStep::Cutoff(Some(estimate));
+        }
+        if state.is_win() {
+            return IdaStep::Found;
+        }
+        if let Some(limit) = self.tracker.exceeded_limit() {
+            return IdaStep::BudgetExceeded(limit);
+        }
+        if self.tracker.expand(state) {
+            self.best = Some(self.line.clone());
+        }
+
+        let mut next_bound = None;
+        for successor in self.tracker.successors(state) {
+            if self.line.states.contains(&successor.state) {
+                continue;
+            }
+            let length = self.line.moves.len();
+            self.line.play(successor.mv);
+            self.line.auto_play(self.tracker);
+            match self.search(bound) {
+                IdaStep::Cutoff(Some(bound)) => {
+                    next_bound = Some(next_bound.map_or(bound, |next: usize| next.min(bound)));
+                }
+                IdaStep::Cutoff(None) => {}
+                found_or_exceeded => return found_or_exceeded,
+            }
+            self.line.states.truncate(length + 1);
+            self.line.moves.truncate(length);
+        }
+
+        IdaStep::Cutoff(next_bound)
+    }
+}
+
+/// Iterative deepening A*, only keeping the current line in memory.
+pub(crate) fn ida_star(start: &State, tracker: &mut Tracker<'_>) -> SearchEnd {
+    let mut bound = tracker.heuristic.estimate(start);
+    tracker.store(start.approximate_size());
+    let mut search = IdaStar {
+        tracker,
+        line: Solution {
+            states: vec![start.clone()],
+            moves: vec![],
+        },
+        best: None,
+    };
+
+    loop {
+        match search.search(bound) {
+            IdaStep::Found => return SearchEnd::Solved(search.line),
+            IdaStep::Cutoff(Some(next_bound)) => bound = next_bound,
+            IdaStep::Cutoff(None) => return SearchEnd::Exhausted(None),
+            IdaStep::BudgetExceeded(limit) => {
+                let partial = search.best.take().unwrap_or_else(|| Solution {
+                    states: vec![start.clone()],
+                    moves: vec![],
+                });
+                return SearchEnd::BudgetExceeded { limit, partial };
+            }
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Scanning ranges of seeds in parallel for deals with a given layout or difficulty, or for the
+//! seed of a known deal.
+
+use std::{
+    ops::ControlFlow,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::ensure;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    batch::SeedRange,
+    config::GameConfig,
+    deck_generator::generate_game_with,
+    denormalized::DenormalizedState,
+    godot_shuffle::{Seed, shuffles_into},
+    solver::{Outcome, SolveResult},
+    state::{Card, State},
+};
+
+/// Criteria a deal must meet, all of them, to be reported. The layout criteria are checked first,
+/// the deal is only searched when a criterion depends on its solution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeedFilter {
+    /// Fewest moves of the solution. Only solvable deals meet a length criterion.
+    pub min_length: Option<usize>,
+    /// Most moves of the solution.
+    pub max_length: Option<usize>,
+    /// Only deals proven impossible to win.
+    pub unsolvable: bool,
+    /// Fewest cards dealt on top of each beast.
+    pub min_beast_depth: Option<usize>,
+    /// Most cards dealt on top of each beast.
+    pub max_beast_depth: Option<usize>,
+    /// Only deals with every pirate in the same column.
+    pub pirates_together: bool,
+}
+
+impl SeedFilter {
+    /// Whether deals must be searched to be checked.
+    pub fn needs_search(&self) -> bool {
+        self.min_length.is_some() || self.max_length.is_some() || self.unsolvable
+    }
+
+    /// Whether the initial `state` meets the layout criteria.
+    pub fn matches_layout(&self, state: &DenormalizedState) -> bool {
+        let mut beast_depths = state.board.iter().flat_map(|stack| {
+            stack
+                .cards
+                .iter()
+                .enumerate()
+                .filter(|(_, card)| matches!(card, Card::Beast(_)))
+                .map(|(index, _)| stack.cards.len() - 1 - index)
+        });
+        let beasts_buried = beast_depths.all(|depth| {
+            self.min_beast_depth.is_none_or(|min| depth >= min)
+                && self.max_beast_depth.is_none_or(|max| depth <= max)
+        });
+
+        let pirate_columns = state
+            .board
+            .iter()
+            .filter(|stack| stack.cards.contains(&Card::Pirate))
+            .count();
+        beasts_buried && (!self.pirates_together || pirate_columns <= 1)
+    }
+
+    /// Whether the search `result` of a deal meets the solution criteria, unknown when the search
+    /// ran out of budget.
+    pub fn matches_result(&self, result: &SolveResult) -> Option<bool> {
+        match &result.outcome {
+            Outcome::Solved(solution) => Some(
+                !self.unsolvable
+                    && self
+                        .min_length
+                        .is_none_or(|min| solution.moves.len() >= min)
+                    && self
+                        .max_length
+                        .is_none_or(|max| solution.moves.len() <= max),
+            ),
+            Outcome::Unsolvable { .. } => Some(self.unsolvable),
+            Outcome::BudgetExceeded { .. } => None,
+        }
+    }
+}
+
+/// A seed whose deal meets a [`SeedFilter`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedMatch {
+    pub seed: Seed,
+    /// Whether the deal can be won, when it was searched.
+    pub solvable: Option<bool>,
+    /// Number of moves of the solution, when one was searched for and found.
+    pub length: Option<usize>,
+}
+
+/// Totals of a scan, see [`find_seeds`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanSummary {
+    /// Number of seeds dealt.
+    pub scanned: usize,
+    /// Number of seeds meeting the filter.
+    pub matched: usize,
+    /// Number of seeds meeting the layout criteria whose search ran out of budget.
+    pub undecided: usize,
+}
+
+/// Deals every seed of `seeds` for the variant described by `config` on the global [`rayon`]
+/// thread pool, searching them with `solve` when the filter needs it, and hands each seed meeting
+/// `filter` to `on_match` as soon as it is found, in no particular order. Stops early once
+/// `on_match` breaks or fails.
+pub fn find_seeds(
+    seeds: &SeedRange,
+    config: &GameConfig,
+    filter: &SeedFilter,
+    solve: impl Fn(&State) -> SolveResult + Sync,
+    on_match: impl Fn(SeedMatch) -> anyhow::Result<ControlFlow<()>> + Sync,
+) -> anyhow::Result<ScanSummary> {
+    let scanned = AtomicUsize::new(0);
+    let matched = AtomicUsize::new(0);
+    let undecided = AtomicUsize::new(0);
+
+    let flow = seeds.par_seeds().try_for_each(|seed| {
+        scanned.fetch_add(1, Ordering::Relaxed);
+        let state = generate_game_with(seed, config);
+        if !filter.matches_layout(&state) {
+            return ControlFlow::Continue(());
+        }
+
+        let found = if filter.needs_search() {
+            let result = solve(&state.normalize());
+            match filter.matches_result(&result) {
+                Some(true) => SeedMatch {
+                    seed,
+                    solvable: Some(result.solution().is_some()),
+                    length: result.solution().map(|solution| solution.moves.len()),
+                },
+                Some(false) => return ControlFlow::Continue(()),
+                None => {
+                    undecided.fetch_add(1, Ordering::Relaxed);
+                    return ControlFlow::Continue(());
+                }
+            }
+        } else {
+            SeedMatch {
+                seed,
+                solvable: None,
+                length: None,
+            }
+        };
+
+        matched.fetch_add(1, Ordering::Relaxed);
+        match on_match(found) {
+            Ok(ControlFlow::Continue(())) => ControlFlow::Continue(()),
+            Ok(ControlFlow::Break(())) => ControlFlow::Break(Ok(())),
+            Err(error) => ControlFlow::Break(Err(error)),
+        }
+    });
+    if let ControlFlow::Break(result) = flow {
+        result?;
+    }
+
+    Ok(ScanSummary {
+        scanned: scanned.into_inner(),
+        matched: matched.into_inner(),
+        undecided: undecided.into_inner(),
+    })
+}
+
+/// Number of seeds [`reverse_seed`] scans between two progress reports.
+const PROGRESS_INTERVAL: u64 = 1 << 22;
+
+/// The deck of the variant described by `config` in the order it was shuffled into before being
+/// dealt as `state`, the cards being dealt one per column in turn. Fails unless `state` is an
+/// untouched deal: every card on the columns, spread as evenly as dealing them does.
+pub fn dealt_deck(state: &DenormalizedState, config: &GameConfig) -> anyhow::Result<Vec<Card>> {
+    state.normalize().is_valid_with(config)?;
+    ensure!(
+        state.placeholders.holes.iter().all(|hole| hole.0.is_none())
+            && config
+                .card_colors()
+                .all(|color| state.output[&color].0 == 0),
+        "a deal in progress, with cards in the slots or the output, cannot be traced to a seed"
+    );
+    let cards = config.card_count();
+    for (index, stack) in state.board.iter().enumerate() {
+        let dealt = cards / config.stacks + usize::from(index < cards % config.stacks);
+        ensure!(
+            stack.cards.len() == dealt,
+            "column {} has {} cards, but dealing gives it {dealt}",
+            index + 1,
+            stack.cards.len()
+        );
+    }
+    Ok((0..cards)
+        .map(|index| state.board[index % config.stacks].cards[index / config.stacks])
+        .collect())
+}
+
+/// Searches `seeds` for the first one dealing exactly `state`, an untouched deal of the variant
+/// described by `config`, on the global [`rayon`] thread pool. `on_progress` is called with the
+/// number of seeds scanned so far every few million seeds, and up to the seed found when it ends.
+pub fn reverse_seed(
+    state: &DenormalizedState,
+    seeds: &SeedRange,
+    config: &GameConfig,
+    on_progress: impl Fn(u64),
+) -> anyhow::Result<Option<Seed>> {
+    let target = dealt_deck(state, config)?;
+    let deck = config.deck();
+
+    let mut scanned = 0;
+    for chunk in seeds.chunks(PROGRESS_INTERVAL) {
+        let found = chunk
+            .par_seeds()
+            .map_init(
+                || deck.clone(),
+                |shuffled, seed| {
+                    shuffled.copy_from_slice(&deck);
+                    shuffles_into(shuffled, seed, &target).then_some(seed)
+                },
+            )
+            .find_first(Option::is_some)
+            .flatten();
+        scanned += match (found, chunk.seeds().next()) {
+            (Some(seed), Some(first)) => (i64::from(seed) - i64::from(first)) as u64 + 1,
+            _ => chunk.seed_count() as u64,
+        };
+        on_progress(scanned);
+        if found.is_some() {
+            return Ok(found);
+        }
+    }
+    Ok(None)
+}
@@ -1,46 +1,358 @@
-use pathfinding::prelude::*;
+//! Search for a winning sequence of moves.
 
-use crate::state::{CardOrBundle, State};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::Move,
+    certificate::UnsolvabilityCertificate,
+    config::GameConfig,
+    portfolio, rules,
+    search::{self, SearchEnd, Tracker},
+    state::{Card, CardOrBundle, Layout, State},
+};
 
 impl State {
-    fn is_win(&self) -> bool {
-        self.board.iter().all(|stack| stack.cards.is_empty())
-    }
-}
-
-pub fn solve(start: &State) -> Option<Vec<State>> {
-    // Faster, but worse solutions
-    //dfs(
-    //start.clone(),
-    //|state: &State| state.get_next_states().collect::<Vec<_>>(),
-    //|state: &State| state.is_win(),
-    //)
-    astar(
-        start,
-        |state: &State| {
-            state
-                .get_next_states()
-                //.inspect(|state| {
-                //dbg!(state);
-                //})
-                .map(|state| (state, 1))
-                .collect::<Vec<_>>()
+    /// Whether the game is won, see [`rules::is_win`].
+    pub fn is_win(&self) -> bool {
+        rules::is_win(self)
+    }
+}
+
+/// A line of play, winning unless returned as a partial result.
+#[derive(Debug, Clone)]
+pub struct Solution {
+    /// Every state from the start to the last one, both included.
+    pub states: Vec<State>,
+    /// The move leading from each state to the next one, one fewer than `states`.
+    pub moves: Vec<Move>,
+}
+
+/// Estimate of the number of moves left to win, guiding the search.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    /// Counts the cards not yet sent to the output plus the beast bundles already made. Usually
+    /// finds short solutions quickly, but can overestimate, so they are not guaranteed minimal.
+    #[default]
+    Fast,
+    /// Never overestimates, so [`solve_with`] returns a solution with the minimum number of
+    /// moves.
+    ///
+    /// Every numbered card left on the board needs its own move to leave it, either to the output
+    /// or to a placeholder. Beasts and pirates only leave the board together in a promotion,
+    /// which takes at most three of them at once, or one at a time into a placeholder. No move
+    /// does both, so the numbered cards on the board plus a third of the beasts and pirates on the
+    /// board, rounded up, is a lower bound. A move changes this bound by at most one, so the
+    /// heuristic is also consistent.
+    Admissible,
+}
+
+impl Heuristic {
+    pub fn estimate(&self, state: &impl Layout) -> usize {
+        match self {
+            Heuristic::Fast => {
+                state
+                    .stacks()
+                    .flat_map(|stack| stack.cards.iter())
+                    .filter(|card| matches!(card, Card::Normal(_)))
+                    .count()
+                    + state
+                        .holes()
+                        .filter(|hole| {
+                            matches!(
+                                hole.0,
+                                Some(
+                                    CardOrBundle::Card(Card::Normal(_))
+                                        | CardOrBundle::BeastBundle(_)
+                                )
+                            )
+                        })
+                        .count()
+            }
+            Heuristic::Admissible => {
+                let (normal, special) = state.stacks().flat_map(|stack| stack.cards.iter()).fold(
+                    (0, 0),
+                    |(normal, special), card| match card {
+                        Card::Normal(_) => (normal + 1, special),
+                        _ => (normal, special + 1),
+                    },
+                );
+                normal + usize::div_ceil(special, 3)
+            }
+        }
+    }
+}
+
+/// Search algorithm used to look for a solution.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SearchStrategy {
+    /// A*, returning minimal solutions with [`Heuristic::Admissible`].
+    #[default]
+    AStar,
+    /// A* with the heuristic multiplied by `weight`, at least 1, trading solution length for speed.
+    /// Solutions are at most `weight` times longer than the minimum with
+    /// [`Heuristic::Admissible`].
+    WeightedAStar { weight: f64 },
+    /// Iterative deepening A*, keeping only the current line in memory. Returns minimal
+    /// solutions with [`Heuristic::Admissible`], but revisits states a lot.
+    IdaStar,
+    /// Always expands the state with the lowest heuristic, ignoring the moves already played.
+    GreedyBestFirst,
+    /// Depth-first search, following moves in generation order. Usually the fastest, with the
+    /// longest solutions.
+    DepthFirst,
+}
+
+/// Moves played right away instead of being searched as separate branches.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AutoPlay {
+    /// Every move is a branch of the search.
+    #[default]
+    Off,
+    /// After each move, sends to the output every card that can never be needed again as a base:
+    /// the 1s and 2s, and cards whose next lower cards of the other colors are already out. These
+    /// outputs still count as moves and are listed in the solution, which stays minimal with
+    /// [`Heuristic::Admissible`].
+    Safe,
+}
+
+impl AutoPlay {
+    /// Plays the automatic moves from `state`, a state of the variant described by `game`,
+    /// until none is left, returning them in order.
+    pub fn play(&self, state: &mut State, game: &GameConfig) -> Vec<Move> {
+        let mut moves = vec![];
+        if let AutoPlay::Safe = self {
+            while let Some(mv) = state.safe_output(game) {
+                state.apply(&mv);
+                moves.push(mv);
+            }
+        }
+        moves
+    }
+}
+
+/// Limits on the resources a search may use. Every limit is disabled by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    /// Wall-clock time.
+    pub time: Option<Duration>,
+    /// Number of states whose successors are generated.
+    pub expanded_nodes: Option<usize>,
+    /// Approximate number of bytes taken by the stored states.
+    pub memory: Option<usize>,
+}
+
+/// The [`Budget`] limit that stopped a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Limit {
+    Time,
+    ExpandedNodes,
+    Memory,
+}
+
+/// How many threads a single search uses.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Parallelism {
+    /// One search on the calling thread.
+    #[default]
+    Off,
+    /// Races up to `searches` variations of the options on the [`rayon`] thread pool: the
+    /// requested strategy first, then other strategies with and without [`AutoPlay`]. Only
+    /// strategies returning minimal solutions join when the requested one does. The first search
+    /// to solve the deal or prove it unsolvable stops the others.
+    ///
+    /// Node and memory budgets are shared evenly between the searches, and the statistics add up
+    /// the work of all of them.
+    Portfolio {
+        searches: usize,
+        /// Returns the result of the first search in the order above that does not run out of
+        /// budget, waiting for the ones before it, so that the outcome does not depend on thread
+        /// timing unless a time limit is set.
+        deterministic: bool,
+    },
+}
+
+/// Everything configurable about a search.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SolverOptions {
+    /// Variant of the game searched.
+    pub game: GameConfig,
+    pub strategy: SearchStrategy,
+    pub heuristic: Heuristic,
+    pub auto_play: AutoPlay,
+    pub budget: Budget,
+    pub parallelism: Parallelism,
+}
+
+impl SolverOptions {
+    /// Whether the solutions found are guaranteed to have the minimum number of moves: with
+    /// [`Heuristic::Admissible`] and a strategy that never settles for a longer line, A* or IDA*.
+    pub fn finds_minimal_solutions(&self) -> bool {
+        self.heuristic == Heuristic::Admissible
+            && matches!(
+                self.strategy,
+                SearchStrategy::AStar | SearchStrategy::IdaStar
+            )
+    }
+}
+
+/// How a search ended.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// A winning line was found.
+    Solved(Solution),
+    /// Every state reachable from the start was explored without finding a win.
+    Unsolvable {
+        /// Number of distinct reachable states, unless the strategy does not keep track of them.
+        reachable_states: Option<usize>,
+    },
+    /// The search ran out of budget. `partial` is the line to the expanded state with the lowest
+    /// heuristic estimate.
+    BudgetExceeded { limit: Limit, partial: Solution },
+}
+
+/// Counters measured during a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statistics {
+    /// Number of states whose successors were generated.
+    pub expanded_nodes: usize,
+    /// Wall-clock time spent searching.
+    pub elapsed: Duration,
+}
+
+/// Result of [`solve_with`].
+#[derive(Debug, Clone)]
+pub struct SolveResult {
+    pub outcome: Outcome,
+    pub statistics: Statistics,
+}
+
+impl SolveResult {
+    /// The winning line, if one was found.
+    pub fn solution(&self) -> Option<&Solution> {
+        match &self.outcome {
+            Outcome::Solved(solution) => Some(solution),
+            _ => None,
+        }
+    }
+}
+
+/// Fixed-point scale of move costs for [`SearchStrategy::WeightedAStar`].
+const WEIGHT_SCALE: f64 = 1000.0;
+
+/// Finds a short sequence of moves from `start` to a winning state with the default
+/// [`SolverOptions`].
+pub fn solve(start: &State) -> Option<Solution> {
+    match solve_with(start, &SolverOptions::default()).outcome {
+        Outcome::Solved(solution) => Some(solution),
+        _ => None,
+    }
+}
+
+/// Searches for a sequence of moves from `start` to a winning state as configured by `options`.
+pub fn solve_with(start: &State, options: &SolverOptions) -> SolveResult {
+    match options.parallelism {
+        Parallelism::Portfolio {
+            searches,
+            deterministic,
+        } if searches > 1 => portfolio::solve(start, options, searches, deterministic),
+        _ => search_with(start, options, None),
+    }
+}
+
+/// Runs the single search configured by `options`, stopping early once `cancelled` returns true.
+pub(crate) fn search_with(
+    start: &State,
+    options: &SolverOptions,
+    cancelled: Option<&dyn Fn() -> bool>,
+) -> SolveResult {
+    let start_time = Instant::now();
+    let mut tracker = Tracker::new(
+        &options.budget,
+        &options.game,
+        options.heuristic,
+        options.auto_play,
+    );
+    if let Some(cancelled) = cancelled {
+        tracker = tracker.cancelled_by(cancelled);
+    }
+
+    let opening = tracker.opening(start);
+    let start = opening.states.last().unwrap();
+    let end = match options.strategy {
+        SearchStrategy::AStar => search::best_first(start, &mut tracker, 1, 1),
+        SearchStrategy::WeightedAStar { weight } => search::best_first(
+            start,
+            &mut tracker,
+            WEIGHT_SCALE as usize,
+            (weight * WEIGHT_SCALE).round() as usize,
+        ),
+        SearchStrategy::IdaStar => search::ida_star(start, &mut tracker),
+        SearchStrategy::GreedyBestFirst => search::best_first(start, &mut tracker, 0, 1),
+        SearchStrategy::DepthFirst => search::depth_first(start, &mut tracker, None),
+    }
+    .after(opening);
+
+    SolveResult {
+        outcome: match end {
+            SearchEnd::Solved(solution) => Outcome::Solved(solution),
+            SearchEnd::Exhausted(reachable_states) => Outcome::Unsolvable { reachable_states },
+            SearchEnd::BudgetExceeded { limit, partial } => {
+                Outcome::BudgetExceeded { limit, partial }
+            }
+        },
+        statistics: Statistics {
+            expanded_nodes: tracker.expanded_nodes,
+            elapsed: start_time.elapsed(),
         },
-        |state: &State| {
-            state
-                .output
-                .by_color
-                .iter()
-                .map(|num| (9 - num.0) as usize)
-                .sum::<usize>()
-                + state
-                    .placeholders
-                    .holes
-                    .iter()
-                    .map(|hole| matches!(hole.0, Some(CardOrBundle::BeastBundle(_))) as usize)
-                    .sum::<usize>()
+    }
+}
+
+/// Result of [`explore`].
+#[derive(Debug, Clone)]
+pub struct Exploration {
+    pub result: SolveResult,
+    /// Proof that no win is reachable, when the exploration was exhaustive.
+    pub certificate: Option<UnsolvabilityCertificate>,
+}
+
+/// Explores every state reachable from `start` in the variant described by `game` depth-first,
+/// stopping at the first win.
+///
+/// [`AutoPlay`] is never used, so the certificate holds every state reachable with any legal move.
+///
+/// Unlike [`solve_with`], an unsolvable outcome comes with a certificate listing every reachable
+/// state, which can be checked independently with [`UnsolvabilityCertificate::check`].
+pub fn explore(start: &State, game: &GameConfig, budget: &Budget) -> Exploration {
+    let start_time = Instant::now();
+    let mut tracker = Tracker::new(budget, game, Heuristic::Admissible, AutoPlay::Off);
+
+    let mut reachable = vec![];
+    let (outcome, certificate) =
+        match search::depth_first(start, &mut tracker, Some(&mut reachable)) {
+            SearchEnd::Solved(solution) => (Outcome::Solved(solution), None),
+            SearchEnd::Exhausted(reachable_states) => (
+                Outcome::Unsolvable { reachable_states },
+                Some(UnsolvabilityCertificate {
+                    start: start.clone(),
+                    reachable,
+                }),
+            ),
+            SearchEnd::BudgetExceeded { limit, partial } => {
+                (Outcome::BudgetExceeded { limit, partial }, None)
+            }
+        };
+
+    Exploration {
+        result: SolveResult {
+            outcome,
+            statistics: Statistics {
+                expanded_nodes: tracker.expanded_nodes,
+                elapsed: start_time.elapsed(),
+            },
         },
-        |state: &State| state.is_win(),
-    )
-    .map(|solution| solution.0)
+        certificate,
+    }
 }
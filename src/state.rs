@@ -1,11 +1,16 @@
+//! Game model shared by the parser, the move generator and the solver.
+
 use std::{
     fmt::Display,
     ops::{Index, IndexMut},
 };
 
-use crate::collection::BTreeMultiSet;
+use serde::{Deserialize, Serialize};
+
+use crate::{collection::BTreeMultiSet, config::GameConfig};
 
-#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq)]
+/// Rank of a [`NormalCard`], from 1 to 9 in the standard game. The [`Output`] uses 0 for an empty foundation.
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CardNumber(pub u8);
 
 impl CardNumber {
@@ -18,7 +23,9 @@ impl CardNumber {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq)]
+/// The three special cards, each of which can be bundled together with two pirates.
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Beast {
     Whale,
     Tentacle,
@@ -35,20 +42,24 @@ impl Display for Beast {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CardColor {
     Red,
     Teal,
     Blue,
+    Green,
 }
 
-#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq)]
+/// A numbered card, which can be stacked and sent to the [`Output`].
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NormalCard {
     pub number: CardNumber,
     pub color: CardColor,
 }
 
-#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Card {
     Pirate,
     Beast(Beast),
@@ -56,6 +67,7 @@ pub enum Card {
 }
 
 impl Card {
+    /// Returns the numbered card, if this is one.
     pub fn normal(&self) -> Option<&NormalCard> {
         if let Card::Normal(normal) = self {
             Some(normal)
@@ -65,27 +77,66 @@ impl Card {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq)]
+/// Content of a [`PlaceHolder`]: a single card or a beast promoted with two pirates.
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CardOrBundle {
     Card(Card),
     BeastBundle(Beast),
 }
 
-#[derive(Debug, Clone, PartialOrd, Ord, Hash, PartialEq, Eq)]
+/// A column of the board, from the bottom card to the exposed one.
+#[derive(Debug, Clone, PartialOrd, Ord, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CardStack {
     pub cards: Vec<Card>,
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+/// Foundations, holding the highest number already sent out for each color, indexed by
+/// [`CardColor`]. Colors a deck does not use stay at 0.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Output {
-    pub by_color: [CardNumber; 3],
+    #[serde(with = "by_color")]
+    pub by_color: [CardNumber; 4],
 }
 
 impl Default for Output {
     fn default() -> Self {
         Self {
-            by_color: [0; 3].map(CardNumber),
+            by_color: [0; 4].map(CardNumber),
+        }
+    }
+}
+
+/// Writes the foundations of the three standard colors, and the others only once they are used,
+/// so that standard games keep serializing as before.
+mod by_color {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+    use super::CardNumber;
+
+    const STANDARD_COLORS: usize = 3;
+
+    pub fn serialize<S: Serializer>(
+        by_color: &[CardNumber; 4],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let used = by_color
+            .iter()
+            .rposition(|number| number.0 > 0)
+            .map_or(0, |last| last + 1);
+        by_color[..used.max(STANDARD_COLORS)].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[CardNumber; 4], D::Error> {
+        let numbers = Vec::<CardNumber>::deserialize(deserializer)?;
+        let mut by_color = [CardNumber(0); 4];
+        if numbers.len() > by_color.len() {
+            return Err(D::Error::invalid_length(numbers.len(), &"at most 4 colors"));
         }
+        by_color[..numbers.len()].copy_from_slice(&numbers);
+        Ok(by_color)
     }
 }
 
@@ -102,25 +153,64 @@ impl IndexMut<&CardColor> for Output {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq)]
+/// A free cell, which can hold one card or a beast bundle.
+#[derive(
+    Debug, Default, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq, Serialize, Deserialize,
+)]
 pub struct PlaceHolder(pub Option<CardOrBundle>);
 
-#[derive(Debug, Clone, PartialOrd, Ord, Hash, PartialEq, Eq)]
+/// Unordered set of placeholders, so that states differing only in slot order compare equal.
+#[derive(Debug, Clone, PartialOrd, Ord, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PlaceHolders {
     pub holes: BTreeMultiSet<PlaceHolder>,
 }
 
-impl Default for PlaceHolders {
-    fn default() -> Self {
+impl PlaceHolders {
+    /// `count` empty placeholders.
+    pub fn empty(count: usize) -> Self {
         Self {
-            holes: BTreeMultiSet::from_iter([PlaceHolder::default(); 3]),
+            holes: BTreeMultiSet::from_iter(vec![PlaceHolder::default(); count]),
         }
     }
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+impl Default for PlaceHolders {
+    fn default() -> Self {
+        Self::empty(GameConfig::STANDARD.slots)
+    }
+}
+
+/// Normalized game state used during search.
+///
+/// Stacks and placeholders are stored as multisets, so their on-screen order is lost. Use
+/// [`DenormalizedState`](crate::denormalized::DenormalizedState) to keep it.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct State {
     pub placeholders: PlaceHolders,
     pub output: Output,
     pub board: BTreeMultiSet<CardStack>,
 }
+
+/// Read access to a game state, whatever the order of its stacks and placeholders.
+///
+/// Moves generated from a layout, e.g. by [`legal_moves`](crate::rules::legal_moves), refer to
+/// stacks and placeholders by their position in its iteration order.
+pub trait Layout {
+    fn stacks(&self) -> impl Iterator<Item = &CardStack>;
+    fn holes(&self) -> impl Iterator<Item = &PlaceHolder>;
+    fn output(&self) -> &Output;
+}
+
+impl Layout for State {
+    fn stacks(&self) -> impl Iterator<Item = &CardStack> {
+        self.board.iter()
+    }
+
+    fn holes(&self) -> impl Iterator<Item = &PlaceHolder> {
+        self.placeholders.holes.iter()
+    }
+
+    fn output(&self) -> &Output {
+        &self.output
+    }
+}
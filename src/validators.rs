@@ -1,62 +1,133 @@
-use std::collections::HashMap;
+//! Sanity checks on states before solving.
 
-use crate::state::{Beast, Card, CardColor, CardNumber, NormalCard, State};
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use crate::{
+    config::{COLORS, GameConfig},
+    state::{Card, CardColor, CardNumber, CardOrBundle, NormalCard, State},
+};
 
 use thiserror::Error;
 
-#[derive(Error, Debug)]
-pub enum InvalidInitialState {
+/// A single inconsistency found in a state.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InvalidState {
     #[error("the {card:?} card count is not valid ({count}, expected = {expected})")]
     InvalidCardAmount {
         card: Card,
         count: usize,
         expected: usize,
     },
+    #[error("the {card:?} card is not part of the deck")]
+    UnknownCard { card: Card },
+    #[error("the {color:?} output is past the last card ({number:?})")]
+    InvalidOutput {
+        color: CardColor,
+        number: CardNumber,
+    },
+    #[error("there are {count} card stacks, expected {expected}")]
+    StackCount { count: usize, expected: usize },
+    #[error("there are {count} placeholders, expected {expected}")]
+    PlaceHolderCount { count: usize, expected: usize },
 }
 
+/// Every inconsistency found in a state, see [`State::is_valid`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{}", .0.iter().join("\n"))]
+pub struct InvalidStateErrors(pub Vec<InvalidState>);
+
 struct CountTrack {
     actual: usize,
     expected: usize,
 }
 
 impl State {
-    pub fn is_valid(&self) -> Result<(), InvalidInitialState> {
-        let cards: Vec<_> = self
+    /// Checks that the state holds exactly the cards of a full standard deck, see
+    /// [`State::is_valid_with`].
+    pub fn is_valid(&self) -> Result<(), InvalidStateErrors> {
+        self.is_valid_with(&GameConfig::STANDARD)
+    }
+
+    /// Checks that the state has the table of `config` and holds exactly the cards of its deck,
+    /// counting the cards on the board, in the placeholders, already sent to the output and
+    /// consumed by beast bundles.
+    pub fn is_valid_with(&self, config: &GameConfig) -> Result<(), InvalidStateErrors> {
+        let mut errors = vec![];
+
+        let stacks = self.board.iter().count();
+        if stacks != config.stacks {
+            errors.push(InvalidState::StackCount {
+                count: stacks,
+                expected: config.stacks,
+            });
+        }
+        let holes = self.placeholders.holes.iter().count();
+        if holes != config.slots {
+            errors.push(InvalidState::PlaceHolderCount {
+                count: holes,
+                expected: config.slots,
+            });
+        }
+
+        let mut counts = BTreeMap::<_, CountTrack>::new();
+        for card in config.deck() {
+            counts
+                .entry(card)
+                .or_insert(CountTrack {
+                    actual: 0,
+                    expected: 0,
+                })
+                .expected += 1;
+        }
+
+        let board_cards = self
             .board
             .iter()
-            .flat_map(|stack| stack.cards.iter())
-            .collect();
-
-        let numbers = (1..=9).map(CardNumber);
-        let colors = [CardColor::Teal, CardColor::Red, CardColor::Blue];
-        let normal_cards = numbers
-            .flat_map(|number| colors.map(|color| Card::Normal(NormalCard { color, number })))
-            .map(|card| (card, 1));
-        let beast_cards = [Beast::Ship, Beast::Whale, Beast::Tentacle]
-            .map(Card::Beast)
-            .map(|card| (card, 1));
-        let pirate_cards = [Card::Pirate].map(|card| (card, 6));
-
-        let mut counts =
-            HashMap::<_, _>::from_iter(normal_cards.chain(beast_cards).chain(pirate_cards).map(
-                |(card, expected)| {
-                    (
-                        card,
-                        CountTrack {
-                            actual: 0,
-                            expected,
-                        },
-                    )
-                },
-            ));
-
-        for card in &cards {
-            counts.get_mut(card).unwrap().actual += 1;
+            .flat_map(|stack| stack.cards.iter().copied());
+
+        let placeholder_cards = self
+            .placeholders
+            .holes
+            .iter()
+            .flat_map(|hole| match hole.0 {
+                None => vec![],
+                Some(CardOrBundle::Card(card)) => vec![card],
+                Some(CardOrBundle::BeastBundle(beast)) => {
+                    vec![Card::Beast(beast), Card::Pirate, Card::Pirate]
+                }
+            });
+
+        let mut output_cards = vec![];
+        for color in COLORS {
+            let number = self.output[&color];
+            let last = if config.card_colors().contains(&color) {
+                config.ranks
+            } else {
+                0
+            };
+            if number > CardNumber(last) {
+                errors.push(InvalidState::InvalidOutput { color, number });
+            }
+            output_cards.extend((1..=number.0.min(last)).map(|n| {
+                Card::Normal(NormalCard {
+                    number: CardNumber(n),
+                    color,
+                })
+            }));
+        }
+
+        for card in board_cards.chain(placeholder_cards).chain(output_cards) {
+            match counts.get_mut(&card) {
+                Some(count) => count.actual += 1,
+                None => errors.push(InvalidState::UnknownCard { card }),
+            }
         }
 
         for (card, CountTrack { actual, expected }) in counts {
             if expected != actual {
-                return Err(InvalidInitialState::InvalidCardAmount {
+                errors.push(InvalidState::InvalidCardAmount {
                     card,
                     count: actual,
                     expected,
@@ -64,6 +135,10 @@ impl State {
             }
         }
 
-        Ok(())
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidStateErrors(errors))
+        }
     }
 }
@@ -0,0 +1,515 @@
+//! Replay of solutions written in the notation of [`DenormalizedState::describe`], explaining
+//! the first illegal move.
+//!
+//! A solution has one move per line, optionally numbered as printed by the solver:
+//!
+//! ```text
+//!   1. move 8t from column 5 to column 3
+//!   2. move 3r (+2) from column 4 to slot 1
+//!   3. promote whale from slot 1 with pirates from column 5 and column 4
+//!   4. send 1t from column 5 to the output
+//! ```
+//!
+//! Blank lines are ignored and `//` starts a comment. The column or slot of a promoted beast may
+//! be left out when a single beast of its kind is exposed.
+
+use std::{fmt::Display, io::BufRead};
+
+use thiserror::Error;
+
+use crate::{
+    actions::{CardSource, Move},
+    denormalized::DenormalizedState,
+    game::Spot,
+    rules,
+    state::{Beast, Card, CardOrBundle, NormalCard},
+};
+
+/// Why a written move cannot be played.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    #[error(
+        "unreadable move, expected e.g. \"move 3r (+2) from column 4 to column 1\", \
+         \"send 1t from slot 2 to the output\" or \"promote whale from column 1 with pirates \
+         from column 2 and slot 3\""
+    )]
+    Unreadable,
+    #[error("there is no {0}")]
+    NoSuchPlace(Spot),
+    #[error("{0} is empty")]
+    Empty(Spot),
+    #[error("{place} holds {found}, not {expected}")]
+    WrongCard {
+        place: Spot,
+        found: String,
+        expected: String,
+    },
+    #[error("{place} holds only {cards} cards")]
+    NotEnoughCards { place: Spot, cards: usize },
+    #[error("cards cannot move from {0} to itself")]
+    SamePlace(Spot),
+    #[error("cards cannot move from a slot to another slot")]
+    SlotToSlot,
+    #[error("only one card fits in {0}")]
+    OneCardPerSlot(Spot),
+    #[error("{0} is not free")]
+    SlotTaken(Spot),
+    #[error("only numbered cards go to the output, not {0}")]
+    NotNumbered(String),
+    #[error("{card} cannot go to the output before {needed}")]
+    OutputOutOfOrder { card: String, needed: String },
+    #[error(
+        "{card} cannot go on {below} in {place}, only on the next higher card of another color"
+    )]
+    CannotStack {
+        card: String,
+        below: String,
+        place: Spot,
+    },
+    #[error("no {0} is exposed")]
+    NoExposedBeast(Beast),
+    #[error("several {0}s are exposed, say which one to promote with \"from column N\"")]
+    AmbiguousBeast(Beast),
+    #[error("the two pirates must come from different places")]
+    SamePirate,
+    #[error("no slot is free for the promoted {0}")]
+    NoFreeSlot(Beast),
+    #[error("not a legal move")]
+    Illegal,
+}
+
+/// A move as written, before checking it against a state.
+enum Written<'a> {
+    Output {
+        card: &'a str,
+        from: Spot,
+    },
+    Move {
+        card: &'a str,
+        count: usize,
+        from: Spot,
+        to: Spot,
+    },
+    Promote {
+        beast: Beast,
+        from: Option<Spot>,
+        pirates: [Spot; 2],
+    },
+}
+
+fn place(kind: &str, number: &str) -> Result<Spot, MoveError> {
+    let index = number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_sub(1))
+        .ok_or(MoveError::Unreadable)?;
+    match kind {
+        "column" => Ok(Spot::Column(index)),
+        "slot" => Ok(Spot::Slot(index)),
+        _ => Err(MoveError::Unreadable),
+    }
+}
+
+fn beast(name: &str) -> Result<Beast, MoveError> {
+    [Beast::Whale, Beast::Tentacle, Beast::Ship]
+        .into_iter()
+        .find(|beast| beast.to_string() == name)
+        .ok_or(MoveError::Unreadable)
+}
+
+/// Number of cards moved along with the named one, written `(+N)`.
+fn extra_cards(written: &str) -> Result<usize, MoveError> {
+    written
+        .strip_prefix("(+")
+        .and_then(|written| written.strip_suffix(')'))
+        .and_then(|count| count.parse().ok())
+        .ok_or(MoveError::Unreadable)
+}
+
+fn parse(line: &str) -> Result<Written<'_>, MoveError> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    Ok(match words[..] {
+        ["send", card, "from", kind, number, "to", "the", "output"] => Written::Output {
+            card,
+            from: place(kind, number)?,
+        },
+        ["move", card, "from", kind, number, "to", to_kind, to_number] => Written::Move {
+            card,
+            count: 1,
+            from: place(kind, number)?,
+            to: place(to_kind, to_number)?,
+        },
+        [
+            "move",
+            card,
+            extra,
+            "from",
+            kind,
+            number,
+            "to",
+            to_kind,
+            to_number,
+        ] => Written::Move {
+            card,
+            count: extra_cards(extra)?
+                .checked_add(1)
+                .ok_or(MoveError::Unreadable)?,
+            from: place(kind, number)?,
+            to: place(to_kind, to_number)?,
+        },
+        [
+            "promote",
+            name,
+            "from",
+            kind,
+            number,
+            "with",
+            "pirates",
+            "from",
+            kind1,
+            number1,
+            "and",
+            kind2,
+            number2,
+        ] => Written::Promote {
+            beast: beast(name)?,
+            from: Some(place(kind, number)?),
+            pirates: [place(kind1, number1)?, place(kind2, number2)?],
+        },
+        [
+            "promote",
+            name,
+            "with",
+            "pirates",
+            "from",
+            kind1,
+            number1,
+            "and",
+            kind2,
+            number2,
+        ] => Written::Promote {
+            beast: beast(name)?,
+            from: None,
+            pirates: [place(kind1, number1)?, place(kind2, number2)?],
+        },
+        _ => return Err(MoveError::Unreadable),
+    })
+}
+
+fn source(spot: Spot) -> CardSource {
+    match spot {
+        Spot::Column(column) => CardSource::Stack(column),
+        Spot::Slot(slot) => CardSource::PlaceHolder(slot),
+        Spot::Output => panic!("cards are never taken from the output"),
+    }
+}
+
+/// Checks `card`, as written, against an actual card.
+fn expect_card(place: Spot, found: &Card, card: &str) -> Result<(), MoveError> {
+    if found.to_string() == card {
+        Ok(())
+    } else {
+        Err(MoveError::WrongCard {
+            place,
+            found: found.to_string(),
+            expected: card.to_string(),
+        })
+    }
+}
+
+impl DenormalizedState {
+    fn check_place(&self, place: Spot) -> Result<(), MoveError> {
+        let exists = match place {
+            Spot::Column(column) => column < self.board.len(),
+            Spot::Slot(slot) => slot < self.placeholders.holes.len(),
+            Spot::Output => true,
+        };
+        exists.then_some(()).ok_or(MoveError::NoSuchPlace(place))
+    }
+
+    /// The exposed card at `place`, a column or slot.
+    fn exposed(&self, place: Spot) -> Result<Card, MoveError> {
+        self.check_place(place)?;
+        match place {
+            Spot::Column(column) => self.board[column].cards.last().copied(),
+            Spot::Slot(slot) => match self.placeholders.holes[slot].0 {
+                Some(CardOrBundle::Card(card)) => Some(card),
+                Some(CardOrBundle::BeastBundle(beast)) => {
+                    return Err(MoveError::WrongCard {
+                        place,
+                        found: format!("a promoted {beast}"),
+                        expected: "a card".to_string(),
+                    });
+                }
+                None => None,
+            },
+            Spot::Output => None,
+        }
+        .ok_or(MoveError::Empty(place))
+    }
+
+    fn check_free_slot(&self, slot: Spot) -> Result<(), MoveError> {
+        match self.exposed(slot) {
+            Err(MoveError::Empty(_)) => Ok(()),
+            Err(MoveError::NoSuchPlace(place)) => Err(MoveError::NoSuchPlace(place)),
+            _ => Err(MoveError::SlotTaken(slot)),
+        }
+    }
+
+    fn check_stacking(&self, card: &Card, column: Spot) -> Result<(), MoveError> {
+        let Spot::Column(index) = column else {
+            unreachable!("cards are only stacked in columns");
+        };
+        match self.board[index].cards.last() {
+            Some(below) if !below.can_stack_with(card) => Err(MoveError::CannotStack {
+                card: card.to_string(),
+                below: below.to_string(),
+                place: column,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn resolve_output(&self, card: &str, from: Spot) -> Result<Move, MoveError> {
+        let found = self.exposed(from)?;
+        expect_card(from, &found, card)?;
+        let Card::Normal(normal) = found else {
+            return Err(MoveError::NotNumbered(card.to_string()));
+        };
+        if !self.output.can_output_card(&normal) {
+            let needed = Card::Normal(NormalCard {
+                color: normal.color,
+                number: self.output[&normal.color].next(),
+            });
+            return Err(MoveError::OutputOutOfOrder {
+                card: card.to_string(),
+                needed: needed.to_string(),
+            });
+        }
+        Ok(Move::Output { from: source(from) })
+    }
+
+    fn resolve_move(
+        &self,
+        card: &str,
+        count: usize,
+        from: Spot,
+        to: Spot,
+    ) -> Result<Move, MoveError> {
+        self.check_place(from)?;
+        self.check_place(to)?;
+        if from == to {
+            return Err(MoveError::SamePlace(from));
+        }
+        match (from, to) {
+            (Spot::Column(column), Spot::Column(to_column)) => {
+                let cards = &self.board[column].cards;
+                if cards.is_empty() {
+                    return Err(MoveError::Empty(from));
+                }
+                if cards.len() < count {
+                    return Err(MoveError::NotEnoughCards {
+                        place: from,
+                        cards: cards.len(),
+                    });
+                }
+                let moved = &cards[cards.len() - count..];
+                expect_card(from, &moved[0], card)?;
+                for (below, above) in moved.iter().zip(&moved[1..]) {
+                    if !below.can_stack_with(above) {
+                        return Err(MoveError::CannotStack {
+                            card: above.to_string(),
+                            below: below.to_string(),
+                            place: from,
+                        });
+                    }
+                }
+                self.check_stacking(&moved[0], to)?;
+                Ok(Move::StackToStack {
+                    from: column,
+                    to: to_column,
+                    count,
+                })
+            }
+            (Spot::Column(column), Spot::Slot(slot)) => {
+                expect_card(from, &self.exposed(from)?, card)?;
+                if count > 1 {
+                    return Err(MoveError::OneCardPerSlot(to));
+                }
+                self.check_free_slot(to)?;
+                Ok(Move::StackToPlaceHolder {
+                    from: column,
+                    to: slot,
+                })
+            }
+            (Spot::Slot(slot), Spot::Column(column)) => {
+                let found = self.exposed(from)?;
+                expect_card(from, &found, card)?;
+                if count > 1 {
+                    return Err(MoveError::OneCardPerSlot(from));
+                }
+                self.check_stacking(&found, to)?;
+                Ok(Move::PlaceHolderToStack {
+                    from: slot,
+                    to: column,
+                })
+            }
+            (Spot::Slot(_), Spot::Slot(_)) => Err(MoveError::SlotToSlot),
+            _ => Err(MoveError::Unreadable),
+        }
+    }
+
+    fn resolve_promotion(
+        &self,
+        beast: Beast,
+        from: Option<Spot>,
+        pirates: [Spot; 2],
+    ) -> Result<Move, MoveError> {
+        let from = match from {
+            Some(from) => {
+                expect_card(from, &self.exposed(from)?, &Card::Beast(beast).to_string())?;
+                from
+            }
+            None => {
+                let columns = (0..self.board.len()).map(Spot::Column);
+                let slots = (0..self.placeholders.holes.len()).map(Spot::Slot);
+                let exposed = columns
+                    .chain(slots)
+                    .filter(|place| self.exposed(*place) == Ok(Card::Beast(beast)))
+                    .collect::<Vec<_>>();
+                match exposed[..] {
+                    [] => return Err(MoveError::NoExposedBeast(beast)),
+                    [from] => from,
+                    _ => return Err(MoveError::AmbiguousBeast(beast)),
+                }
+            }
+        };
+        for pirate in pirates {
+            expect_card(pirate, &self.exposed(pirate)?, &Card::Pirate.to_string())?;
+        }
+        if pirates[0] == pirates[1] {
+            return Err(MoveError::SamePirate);
+        }
+        let pirates = pirates.map(source);
+        self.legal_moves()
+            .find(|mv| match mv {
+                Move::PromoteBeast {
+                    beast,
+                    pirates: [pirate1, pirate2],
+                    ..
+                } => {
+                    *beast == source(from)
+                        && (pirates == [*pirate1, *pirate2] || pirates == [*pirate2, *pirate1])
+                }
+                _ => false,
+            })
+            .ok_or(MoveError::NoFreeSlot(beast))
+    }
+
+    /// Reads a move written as by [`DenormalizedState::describe`], checking that it can be
+    /// played from `self`.
+    pub fn parse_move(&self, written: &str) -> Result<Move, MoveError> {
+        let mv = match parse(written)? {
+            Written::Output { card, from } => self.resolve_output(card, from)?,
+            Written::Move {
+                card,
+                count,
+                from,
+                to,
+            } => self.resolve_move(card, count, from, to)?,
+            Written::Promote {
+                beast,
+                from,
+                pirates,
+            } => self.resolve_promotion(beast, from, pirates)?,
+        };
+        if self.legal_moves().any(|legal| legal == mv) {
+            Ok(mv)
+        } else {
+            Err(MoveError::Illegal)
+        }
+    }
+}
+
+/// The first move of a written solution that cannot be played.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("move {number} \"{written}\" (line {line}): {error}")]
+pub struct IllegalMove {
+    /// Position of the move in the solution, from 1.
+    pub number: usize,
+    pub line: usize,
+    pub written: String,
+    pub error: MoveError,
+}
+
+/// Why a solution could not be verified.
+#[derive(Error, Debug)]
+pub enum VerifyError {
+    #[error("reading the solution")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    IllegalMove(#[from] IllegalMove),
+}
+
+/// A written solution replayed move by move.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub moves: Vec<Move>,
+    /// The state after the last move.
+    pub end: DenormalizedState,
+}
+
+impl Replay {
+    /// Whether the end position is won, see [`rules::is_win`].
+    pub fn is_won(&self) -> bool {
+        rules::is_win(&self.end)
+    }
+}
+
+impl Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let moves = self.moves.len();
+        if self.is_won() {
+            write!(f, "{moves} legal moves, winning the game")
+        } else {
+            write!(f, "{moves} legal moves, but the game is not won")
+        }
+    }
+}
+
+/// Replays the solution read from `read`, one move per line, from `start`.
+pub fn verify(start: &DenormalizedState, read: impl BufRead) -> Result<Replay, VerifyError> {
+    let mut replay = Replay {
+        moves: vec![],
+        end: start.clone(),
+    };
+    for (line_number, line) in read.lines().enumerate() {
+        let line = line?;
+        let written = match line.find("//") {
+            Some(comment_start) => &line[..comment_start],
+            None => &line,
+        }
+        .trim();
+        // drop the numbering printed by the solver
+        let written = match written.split_once(". ") {
+            Some((number, written)) if number.parse::<usize>().is_ok() => written.trim(),
+            _ => written,
+        };
+        if written.is_empty() {
+            continue;
+        }
+
+        let mv = replay
+            .end
+            .parse_move(written)
+            .map_err(|error| IllegalMove {
+                number: replay.moves.len() + 1,
+                line: line_number + 1,
+                written: written.to_string(),
+                error,
+            })?;
+        replay.end.apply(&mv);
+        replay.moves.push(mv);
+    }
+    Ok(replay)
+}
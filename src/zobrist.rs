@@ -0,0 +1,128 @@
+//! Zobrist hashing of states, independent of the order of their stacks and placeholders.
+//!
+//! Each card gets a random key for every depth it can have in a stack, and a stack hashes to the
+//! mixed XOR of the keys of its cards. Placeholders and the output of each color also get a key
+//! per content. A state hashes to the wrapping sum of the hashes of its stacks, placeholders and
+//! outputs, which does not depend on their order and can be updated one card at a time.
+
+use crate::{
+    config::{COLORS, MAX_CARDS, MAX_RANKS},
+    packed::{card_code, placeholder_code},
+    state::{Card, CardColor, CardNumber, CardStack, Layout, PlaceHolder, State},
+};
+
+/// Deepest position in a stack with its own keys, enough for the largest deck on a single stack.
+const MAX_DEPTH: usize = MAX_CARDS;
+/// Number of card codes, see [`card_code`].
+const CARD_CODES: usize = 64;
+/// Number of placeholder codes, see [`placeholder_code`].
+const PLACEHOLDER_CODES: usize = 128;
+/// Number of output values of a color, an empty output included.
+const OUTPUT_NUMBERS: usize = MAX_RANKS as usize + 1;
+
+struct Keys {
+    cards: [[u64; CARD_CODES]; MAX_DEPTH],
+    placeholders: [u64; PLACEHOLDER_CODES],
+    outputs: [[u64; OUTPUT_NUMBERS]; COLORS.len()],
+}
+
+const fn split_mix(seed: &mut u64) -> u64 {
+    *seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    mix(*seed)
+}
+
+/// Scrambles the bits of `x`, mapping 0 to 0.
+const fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+static KEYS: Keys = {
+    let mut seed = 0;
+    let mut keys = Keys {
+        cards: [[0; CARD_CODES]; MAX_DEPTH],
+        placeholders: [0; PLACEHOLDER_CODES],
+        outputs: [[0; OUTPUT_NUMBERS]; COLORS.len()],
+    };
+
+    let mut depth = 0;
+    while depth < MAX_DEPTH {
+        let mut code = 0;
+        while code < CARD_CODES {
+            keys.cards[depth][code] = split_mix(&mut seed);
+            code += 1;
+        }
+        depth += 1;
+    }
+    let mut code = 0;
+    while code < PLACEHOLDER_CODES {
+        keys.placeholders[code] = split_mix(&mut seed);
+        code += 1;
+    }
+    let mut color = 0;
+    while color < COLORS.len() {
+        let mut number = 0;
+        while number < OUTPUT_NUMBERS {
+            keys.outputs[color][number] = split_mix(&mut seed);
+            number += 1;
+        }
+        color += 1;
+    }
+
+    keys
+};
+
+/// Key of `card` at `depth` in a stack, 0 being the bottom.
+pub(crate) fn card_key(depth: usize, card: &Card) -> u64 {
+    KEYS.cards[depth][card_code(card) as usize]
+}
+
+/// XOR of the keys of the cards of `stack`, before mixing with [`stack_hash`].
+pub(crate) fn stack_key(stack: &CardStack) -> u64 {
+    stack
+        .cards
+        .iter()
+        .enumerate()
+        .fold(0, |key, (depth, card)| key ^ card_key(depth, card))
+}
+
+/// Contribution of a stack with the given [`stack_key`] to the hash of a state.
+pub(crate) fn stack_hash(key: u64) -> u64 {
+    mix(key)
+}
+
+/// Contribution of `hole` to the hash of a state.
+pub(crate) fn placeholder_hash(hole: &PlaceHolder) -> u64 {
+    KEYS.placeholders[placeholder_code(hole) as usize]
+}
+
+/// Contribution of the output of `color` to the hash of a state.
+pub(crate) fn output_hash(color: CardColor, number: CardNumber) -> u64 {
+    KEYS.outputs[color as usize][number.0 as usize]
+}
+
+/// Hashes `layout` from scratch.
+pub(crate) fn hash(layout: &impl Layout) -> u64 {
+    let stacks = layout
+        .stacks()
+        .map(|stack| stack_hash(stack_key(stack)))
+        .fold(0, u64::wrapping_add);
+    let placeholders = layout
+        .holes()
+        .map(placeholder_hash)
+        .fold(0, u64::wrapping_add);
+    let output = COLORS
+        .map(|color| output_hash(color, layout.output()[&color]))
+        .into_iter()
+        .fold(0, u64::wrapping_add);
+
+    stacks.wrapping_add(placeholders).wrapping_add(output)
+}
+
+impl State {
+    /// Zobrist hash of this state, the same for every order of its stacks and placeholders.
+    pub fn zobrist_hash(&self) -> u64 {
+        hash(self)
+    }
+}
@@ -0,0 +1,141 @@
+//! Checks the records written by batches and their resumption.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use solitaire_solver::{
+    batch::{Deal, Record, RecordFormat, RecordWriter, SeedRange, read_deals, solve_deals},
+    config::GameConfig,
+    report::OutcomeKind,
+    solver::{SearchStrategy, SolverOptions, solve_with},
+};
+
+fn results_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "solitaire_solver_batch_{}_{name}",
+        std::process::id()
+    ));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn run(deals: &[Deal], path: &Path, format: RecordFormat) -> Vec<Record> {
+    let options = SolverOptions {
+        strategy: SearchStrategy::DepthFirst,
+        ..Default::default()
+    };
+    let (writer, done) = RecordWriter::resume(path, format).unwrap();
+    let deals = deals
+        .iter()
+        .filter(|deal| !done.contains(&deal.name))
+        .cloned()
+        .collect::<Vec<_>>();
+    let writer = Mutex::new(writer);
+    let written = Mutex::new(vec![]);
+    solve_deals(
+        &deals,
+        &options.game,
+        |state| solve_with(state, &options),
+        |record| {
+            writer.lock().unwrap().write(&record)?;
+            written.lock().unwrap().push(record);
+            Ok(())
+        },
+    )
+    .unwrap();
+    written.into_inner().unwrap()
+}
+
+fn seeds(range: &str) -> Vec<Deal> {
+    range
+        .parse::<SeedRange>()
+        .unwrap()
+        .seeds()
+        .map(Deal::from)
+        .collect()
+}
+
+#[test]
+fn seed_ranges() {
+    let names = |range| {
+        seeds(range)
+            .into_iter()
+            .map(|deal| deal.name)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("_2..1"), ["_2", "_1", "0"]);
+    assert_eq!(names("_2..=1"), ["_2", "_1", "0", "1"]);
+    assert!(names("3..3").is_empty());
+    assert!("3".parse::<SeedRange>().is_err());
+}
+
+#[test]
+fn every_deal_gets_one_record() {
+    for format in [RecordFormat::Csv, RecordFormat::JsonLines] {
+        let path = results_path(&format!("{format:?}"));
+        let records = run(&seeds("1..=4"), &path, format);
+        assert_eq!(records.len(), 4);
+        for record in &records {
+            assert_eq!(record.outcome, OutcomeKind::Solved);
+            assert_eq!(record.solvable, Some(true));
+            assert!(record.length.is_some());
+        }
+
+        let (_, done) = RecordWriter::resume(&path, format).unwrap();
+        assert_eq!(done.len(), 4);
+        for name in ["1", "2", "3", "4"] {
+            assert!(done.contains(name));
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn resuming_skips_recorded_deals_and_drops_partial_lines() {
+    let path = results_path("resume.csv");
+    run(&seeds("1..3"), &path, RecordFormat::Csv);
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap()
+        .write_all(b"3,sol")
+        .unwrap();
+
+    let records = run(&seeds("1..5"), &path, RecordFormat::Csv);
+    let mut names = records
+        .iter()
+        .map(|record| record.deal.as_str())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["3", "4"]);
+
+    let content = fs::read_to_string(&path).unwrap();
+    let lines = content.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("deal,"));
+    assert!(lines.iter().all(|line| !line.contains(",sol,")));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn deal_lists_mix_seeds_and_files() {
+    let list = "// seeds and files\n42\n\ntrivial_sample.txt // a file\n_7\n";
+    let deals = read_deals(list.as_bytes(), Path::new("samples"), &GameConfig::STANDARD).unwrap();
+    let names = deals
+        .iter()
+        .map(|deal| deal.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["42", "trivial_sample.txt", "_7"]);
+    assert!(
+        read_deals(
+            "missing.txt".as_bytes(),
+            Path::new("samples"),
+            &GameConfig::STANDARD
+        )
+        .is_err()
+    );
+}
@@ -0,0 +1,93 @@
+//! Checks that searches stop within their budget with a usable partial line.
+
+use std::time::Duration;
+
+use solitaire_solver::{
+    deck_generator::generate_game,
+    solver::{Budget, Heuristic, Limit, Outcome, SearchStrategy, SolverOptions, solve_with},
+    state::State,
+};
+
+const STRATEGIES: [SearchStrategy; 5] = [
+    SearchStrategy::AStar,
+    SearchStrategy::WeightedAStar { weight: 2.0 },
+    SearchStrategy::IdaStar,
+    SearchStrategy::GreedyBestFirst,
+    SearchStrategy::DepthFirst,
+];
+
+fn start() -> State {
+    generate_game("_3662067111875154814".parse().unwrap()).normalize()
+}
+
+fn exceeded(budget: Budget, strategy: SearchStrategy) -> Limit {
+    let start = start();
+    let options = SolverOptions {
+        strategy,
+        heuristic: Heuristic::Admissible,
+        budget,
+        ..Default::default()
+    };
+    let result = solve_with(&start, &options);
+    let Outcome::BudgetExceeded { limit, partial } = result.outcome else {
+        panic!(
+            "{strategy:?} should run out of budget, got {:?}",
+            result.outcome
+        );
+    };
+
+    let mut replayed = start.clone();
+    for (mv, expected) in partial.moves.iter().zip(&partial.states[1..]) {
+        replayed.apply(mv);
+        assert_eq!(&replayed, expected);
+    }
+    let best_estimate = Heuristic::Admissible.estimate(&replayed);
+    assert!(best_estimate <= Heuristic::Admissible.estimate(&start));
+
+    limit
+}
+
+#[test]
+fn node_limit() {
+    for strategy in STRATEGIES {
+        let budget = Budget {
+            expanded_nodes: Some(50),
+            ..Default::default()
+        };
+        assert_eq!(exceeded(budget, strategy), Limit::ExpandedNodes);
+    }
+}
+
+#[test]
+fn memory_limit() {
+    for strategy in STRATEGIES {
+        let budget = Budget {
+            memory: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(exceeded(budget, strategy), Limit::Memory);
+    }
+}
+
+#[test]
+fn time_limit() {
+    for strategy in STRATEGIES {
+        let budget = Budget {
+            time: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        assert_eq!(exceeded(budget, strategy), Limit::Time);
+    }
+}
+
+#[test]
+fn expanded_nodes_are_counted() {
+    let options = SolverOptions {
+        budget: Budget {
+            expanded_nodes: Some(50),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(solve_with(&start(), &options).statistics.expanded_nodes, 50);
+}
@@ -0,0 +1,76 @@
+//! Checks unsolvability proofs produced by exhaustive exploration.
+
+use solitaire_solver::{
+    certificate::{CertificateError, UnsolvabilityCertificate},
+    config::GameConfig,
+    parser::read_from,
+    solver::{Budget, Outcome, explore},
+    state::State,
+};
+
+fn load(position: &str) -> State {
+    let state = read_from(position.as_bytes()).unwrap().normalize();
+    state.is_valid().unwrap();
+    state
+}
+
+fn certificate() -> UnsolvabilityCertificate {
+    let exploration = explore(
+        &load(include_str!("../samples/unsolvable_sample.txt")),
+        &GameConfig::STANDARD,
+        &Budget::default(),
+    );
+    assert!(matches!(
+        exploration.result.outcome,
+        Outcome::Unsolvable {
+            reachable_states: Some(12)
+        }
+    ));
+    exploration.certificate.unwrap()
+}
+
+#[test]
+fn unsolvable_deal_is_certified() {
+    certificate().check().unwrap();
+}
+
+#[test]
+fn certificate_round_trips() {
+    let certificate = certificate();
+    let mut written = vec![];
+    certificate.write_to(&mut written).unwrap();
+
+    let read = UnsolvabilityCertificate::read_from(written.as_slice()).unwrap();
+    assert_eq!(read.start, certificate.start);
+    assert_eq!(read.reachable, certificate.reachable);
+    read.check().unwrap();
+}
+
+#[test]
+fn incomplete_certificate_is_rejected() {
+    let mut certificate = certificate();
+    let start = certificate.start.clone();
+    certificate.reachable.retain(|state| state != &start);
+    assert!(matches!(
+        certificate.check(),
+        Err(CertificateError::MissingStart)
+    ));
+
+    let mut certificate = self::certificate();
+    certificate.reachable.truncate(1);
+    assert!(matches!(
+        certificate.check(),
+        Err(CertificateError::NotClosed { index: 0 })
+    ));
+}
+
+#[test]
+fn solvable_deal_is_not_certified() {
+    let exploration = explore(
+        &load(include_str!("../samples/mid_game_sample.txt")),
+        &GameConfig::STANDARD,
+        &Budget::default(),
+    );
+    assert!(matches!(exploration.result.outcome, Outcome::Solved(_)));
+    assert!(exploration.certificate.is_none());
+}
@@ -0,0 +1,150 @@
+//! Checks how the command line tool combines its options.
+
+use std::process::{Command, Output};
+
+/// A variant small enough to be searched in a moment with any option.
+const SMALL: [&str; 10] = [
+    "--ranks",
+    "3",
+    "--colors",
+    "2",
+    "--pirates",
+    "2",
+    "--columns",
+    "3",
+    "--slots",
+    "1",
+];
+
+/// Runs the command line tool on the [`SMALL`] variant with `args`.
+fn run(args: &[&str]) -> Output {
+    run_variant(&SMALL, args)
+}
+
+/// Runs the command line tool on the variant given by the options `variant` with `args`.
+fn run_variant(variant: &[&str], args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_solitaire_solver"))
+        .args(variant)
+        .args(args)
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn weights_below_one_are_rejected() {
+    for weight in ["-5", "0", "0.5", "NaN", "inf"] {
+        let output = run(&[
+            "--strategy",
+            "weighted-astar",
+            &format!("--weight={weight}"),
+            "seed",
+            "0",
+        ]);
+        assert!(!output.status.success(), "--weight={weight}");
+        assert!(stderr(&output).contains("--weight"), "--weight={weight}");
+    }
+
+    let output = run(&[
+        "--strategy",
+        "weighted-astar",
+        "--weight=1",
+        "--format",
+        "moves",
+        "seed",
+        "0",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("1. "));
+}
+
+/// Writes the deal of seed 0 of the [`SMALL`] variant to a card file named after `name`.
+fn small_deal_file(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "solitaire_solver_cli_{}_{name}.txt",
+        std::process::id()
+    ));
+    std::fs::write(&path, "p 3r t\n1r p 2r\n3t 1t 2t\n").unwrap();
+    path
+}
+
+#[test]
+fn hints_only_claim_the_fewest_moves_when_searched_for() {
+    let file = small_deal_file("hint");
+    let file = file.to_str().unwrap();
+    let cases: [(&[&str], bool); 7] = [
+        (&["--optimal"], true),
+        (&["--optimal", "--strategy", "idastar"], true),
+        (&["--optimal", "--strategy", "dfs"], false),
+        (&["--optimal", "--strategy", "greedy"], false),
+        (&["--optimal", "--strategy", "weighted-astar"], false),
+        (&["--optimal", "--exhaustive"], false),
+        (&[], false),
+    ];
+    for (options, minimal) in cases {
+        let output = run(&[options, &["hint", file]].concat());
+        assert!(output.status.success(), "{options:?}: {}", stderr(&output));
+        assert_eq!(
+            stdout(&output).contains("the fewest possible"),
+            minimal,
+            "{options:?}"
+        );
+    }
+}
+
+#[test]
+fn length_filters_need_a_search_for_the_fewest_moves() {
+    let filter = ["find-seeds", "0..=5", "--max-length", "11"];
+    for options in [&["--optimal"][..], &["--optimal", "--strategy", "idastar"]] {
+        let output = run(&[options, &filter].concat());
+        assert!(output.status.success(), "{options:?}: {}", stderr(&output));
+        assert!(
+            stdout(&output)
+                .lines()
+                .any(|line| line == "0 won in 11 moves"),
+            "{options:?}: {}",
+            stdout(&output)
+        );
+    }
+
+    let rejected: [&[&str]; 5] = [
+        &[],
+        &["--optimal", "--exhaustive"],
+        &["--optimal", "--strategy", "dfs"],
+        &["--optimal", "--strategy", "greedy"],
+        &["--optimal", "--strategy", "weighted-astar"],
+    ];
+    for options in rejected {
+        let output = run(&[options, &filter].concat());
+        assert!(!output.status.success(), "{options:?}");
+        assert!(stderr(&output).contains("--max-length"), "{options:?}");
+    }
+}
+
+#[test]
+fn exhaustive_searches_pack_the_cards_of_every_color() {
+    let four_colors = ["--ranks", "3", "--colors", "4", "--columns", "4"];
+    let exploring = ["--exhaustive", "--node-limit", "1000"];
+    let results = std::env::temp_dir().join(format!(
+        "solitaire_solver_cli_{}_four_colors.csv",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&results);
+    let cases: [&[&str]; 3] = [
+        &["seed", "1"],
+        &["batch", "--seeds", "0..2", results.to_str().unwrap()],
+        &["find-seeds", "0..2", "--unsolvable"],
+    ];
+    for command in cases {
+        let output = run_variant(&four_colors, &[&exploring, command].concat());
+        assert!(output.status.success(), "{command:?}: {}", stderr(&output));
+    }
+}
@@ -0,0 +1,117 @@
+//! Checks games played by hand through on-screen moves.
+
+use solitaire_solver::{
+    actions::{CardSource, Move},
+    deck_generator::generate_game,
+    game::{Game, GameError, Spot},
+    parser::read_from,
+    solver::{SearchStrategy, SolverOptions, solve_with},
+};
+
+fn seed_game() -> Game {
+    Game::new(generate_game("12345".parse().unwrap()))
+}
+
+#[test]
+fn solver_lines_can_be_played_undone_and_redone() {
+    let mut game = seed_game();
+    let options = SolverOptions {
+        strategy: SearchStrategy::DepthFirst,
+        ..Default::default()
+    };
+    let start = game.state().clone();
+    let result = solve_with(&start.normalize(), &options);
+    let line = start.replay(result.solution().unwrap());
+
+    for mv in &line.moves {
+        game.play(*mv).unwrap();
+    }
+    assert!(game.is_won());
+    assert_eq!(game.moves_played(), line.moves.len());
+
+    for (mv, state) in line.moves.iter().zip(&line.states).rev() {
+        assert_eq!(game.undo(), Ok(*mv));
+        assert_eq!(game.state(), state);
+    }
+    assert_eq!(game.undo(), Err(GameError::NothingToUndo));
+
+    for (mv, state) in line.moves.iter().zip(&line.states[1..]) {
+        assert_eq!(game.redo(), Ok(*mv));
+        assert_eq!(game.state(), state);
+    }
+    assert_eq!(game.redo(), Err(GameError::NothingToRedo));
+}
+
+#[test]
+fn playing_forgets_the_moves_taken_back() {
+    let mut game = seed_game();
+    let moves = game.state().legal_moves().collect::<Vec<_>>();
+    game.play(moves[0]).unwrap();
+    game.undo().unwrap();
+    game.play(moves[1]).unwrap();
+    assert_eq!(game.redo(), Err(GameError::NothingToRedo));
+    assert_eq!(game.moves_played(), 1);
+}
+
+#[test]
+fn illegal_moves_are_rejected() {
+    let mut game = seed_game();
+    let before = game.state().clone();
+    let mv = Move::StackToStack {
+        from: 0,
+        to: 0,
+        count: 1,
+    };
+    assert_eq!(game.play(mv), Err(GameError::IllegalMove));
+    assert_eq!(game.state(), &before);
+    assert_eq!(game.moves_played(), 0);
+}
+
+#[test]
+fn moves_are_found_between_spots() {
+    let game = Game::new(
+        read_from(
+            "\
+            slots: p - -
+            output: 3r 3t 3b
+
+            9r 8t 7b 6r 5t 4b
+            p w
+            9t 8b 7r 6t 5b 4r
+            p
+            9b 8r 7t 6b 5r 4t
+            -
+            "
+            .as_bytes(),
+        )
+        .unwrap(),
+    );
+    assert_eq!(
+        game.find_move(Spot::Column(0), Spot::Output, None),
+        Some(Move::Output {
+            from: CardSource::Stack(0)
+        })
+    );
+    assert_eq!(game.find_move(Spot::Column(1), Spot::Output, None), None);
+    assert_eq!(game.movable_counts(0), [1, 2, 3, 4, 5, 6]);
+    assert_eq!(
+        game.find_move(Spot::Column(0), Spot::Column(5), None),
+        Some(Move::StackToStack {
+            from: 0,
+            to: 5,
+            count: 6
+        })
+    );
+    assert_eq!(
+        game.find_move(Spot::Column(0), Spot::Column(5), Some(2)),
+        Some(Move::StackToStack {
+            from: 0,
+            to: 5,
+            count: 2
+        })
+    );
+    assert!(matches!(
+        game.find_move(Spot::Column(1), Spot::Slot(1), None),
+        Some(Move::PromoteBeast { into: 1, .. })
+    ));
+}
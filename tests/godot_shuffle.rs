@@ -0,0 +1,106 @@
+//! Regression tests of the deals: snapshots of the shuffle of known seeds, the order of the deck
+//! before shuffling and how seeds are written.
+
+use solitaire_solver::{
+    batch::SeedRange, config::GameConfig, deck_generator::generate_game, godot_shuffle::Seed,
+    parser::read_from,
+};
+
+/// Every recorded deal, with the seed it is dealt from.
+fn snapshots() -> Vec<(String, String)> {
+    let mut deals: Vec<(String, String)> = vec![];
+    for line in include_str!("data/shuffle_snapshots.txt").lines() {
+        if let Some(seed) = line.strip_prefix("seed ") {
+            deals.push((seed.to_string(), String::new()));
+        } else if let Some((_, cards)) = deals.last_mut() {
+            cards.push_str(line);
+            cards.push('\n');
+        }
+    }
+    deals
+}
+
+#[test]
+fn known_seeds_deal_the_recorded_snapshots() {
+    let deals = snapshots();
+    assert!(deals.iter().any(|(seed, _)| seed.starts_with('_')));
+    for (seed, cards) in deals {
+        let expected = read_from(cards.as_bytes()).unwrap();
+        assert_eq!(
+            generate_game(seed.parse().unwrap()),
+            expected,
+            "seed {seed}"
+        );
+    }
+}
+
+#[test]
+fn the_deck_is_shuffled_from_the_recorded_order() {
+    let deck = GameConfig::STANDARD
+        .deck()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    assert_eq!(
+        deck,
+        "1t 1r 1b 2t 2r 2b 3t 3r 3b 4t 4r 4b 5t 5r 5b 6t 6r 6b 7t 7r 7b 8t 8r 8b 9t 9r 9b \
+         p p p p p p t w s"
+    );
+}
+
+#[test]
+fn seeds_are_read_as_the_game_writes_them() {
+    let valid = [
+        ("0", 0),
+        ("_0", 0),
+        ("42", 42),
+        ("_42", -42),
+        ("9223372036854775807", i64::MAX),
+        ("_9223372036854775807", -i64::MAX),
+        ("_9223372036854775808", i64::MIN),
+        ("_3662067111875154814", -3662067111875154814),
+    ];
+    for (text, value) in valid {
+        let seed = text.parse::<Seed>().unwrap();
+        assert_eq!(seed, Seed::from(value), "{text}");
+        assert_eq!(seed.to_string(), text.replace("_0", "0"), "{text}");
+    }
+
+    let invalid = [
+        "",
+        "_",
+        "__5",
+        "-5",
+        "5_",
+        " 5",
+        "0x10",
+        "9223372036854775808",
+        "_9223372036854775809",
+        "18446744073709551615",
+        "_18446744073709551616",
+    ];
+    for text in invalid {
+        assert!(text.parse::<Seed>().is_err(), "{text:?}");
+    }
+}
+
+#[test]
+fn seed_ranges_reach_the_extreme_seeds() {
+    let names = |range: &str| {
+        range
+            .parse::<SeedRange>()
+            .unwrap()
+            .seeds()
+            .map(|seed| seed.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names("_9223372036854775808.._9223372036854775806"),
+        ["_9223372036854775808", "_9223372036854775807"]
+    );
+    assert_eq!(
+        names("9223372036854775806..=9223372036854775807"),
+        ["9223372036854775806", "9223372036854775807"]
+    );
+}
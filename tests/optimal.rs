@@ -0,0 +1,229 @@
+//! Checks the search strategies against an exhaustive breadth-first search on small positions.
+
+use itertools::Itertools;
+use pathfinding::prelude::bfs;
+use solitaire_solver::{
+    config::GameConfig,
+    parser::read_from,
+    solver::{
+        AutoPlay, Budget, Heuristic, Outcome, Parallelism, SearchStrategy, Solution, SolverOptions,
+        solve_with,
+    },
+    state::State,
+};
+
+/// Late game positions taken from solutions of real deals, with the number of moves left.
+const CORPUS: &[(&str, usize)] = &[
+    (
+        "slots: #t #w #s
+        output: 9r 6t 6b
+        -
+        -
+        -
+        9b 8t 7b
+        9t
+        8b 7t",
+        6,
+    ),
+    (
+        "slots: #t #w -
+        output: 9r 5t 4b
+        p
+        5b 6t p
+        s
+        9b 8t 7b
+        9t
+        8b 7t 6b",
+        10,
+    ),
+    (
+        "slots: #t #w -
+        output: 7r 5t 4b
+        -
+        5b 6t p 9r p 7b
+        s
+        9b 8t
+        9t 8r
+        8b 7t 6b",
+        14,
+    ),
+    (
+        "slots: #w s #t
+        output: 9r 8t 3b
+        p p 5b
+        9b
+        9t
+        6b
+        4b 8b
+        7b",
+        10,
+    ),
+    (
+        "slots: #w s #t
+        output: 6r 7t 3b
+        p p 5b
+        9b 8t 7r
+        9t 8r
+        6b
+        4b 8b 9r
+        7b",
+        14,
+    ),
+];
+
+const ADMISSIBLE_ASTAR: SolverOptions = SolverOptions {
+    game: GameConfig::STANDARD,
+    strategy: SearchStrategy::AStar,
+    heuristic: Heuristic::Admissible,
+    auto_play: AutoPlay::Off,
+    budget: Budget {
+        time: None,
+        expanded_nodes: None,
+        memory: None,
+    },
+    parallelism: Parallelism::Off,
+};
+
+fn corpus() -> impl Iterator<Item = (State, usize)> {
+    CORPUS.iter().map(|(position, moves_left)| {
+        let state = read_from(position.as_bytes()).unwrap().normalize();
+        state.is_valid().unwrap();
+        (state, *moves_left)
+    })
+}
+
+fn solve(state: &State, options: &SolverOptions) -> Solution {
+    match solve_with(state, options).outcome {
+        Outcome::Solved(solution) => solution,
+        outcome => panic!("{state:?} not solved: {outcome:?}"),
+    }
+}
+
+fn shortest_solution_length(start: &State) -> usize {
+    let path = bfs(
+        start,
+        |state: &State| {
+            state
+                .get_next_states()
+                .map(|(_, state)| state)
+                .collect_vec()
+        },
+        |state: &State| state.is_win(),
+    )
+    .expect("corpus positions are solvable");
+    path.len() - 1
+}
+
+#[test]
+fn corpus_lengths_match_breadth_first_search() {
+    for (state, moves_left) in corpus() {
+        assert_eq!(shortest_solution_length(&state), moves_left, "{state:?}");
+    }
+}
+
+#[test]
+fn admissible_heuristic_finds_minimum_solutions() {
+    for (state, moves_left) in corpus() {
+        let solution = solve(&state, &ADMISSIBLE_ASTAR);
+        assert_eq!(solution.moves.len(), moves_left, "{state:?}");
+    }
+}
+
+#[test]
+fn admissible_ida_star_finds_minimum_solutions() {
+    let options = SolverOptions {
+        strategy: SearchStrategy::IdaStar,
+        ..ADMISSIBLE_ASTAR
+    };
+    for (state, moves_left) in corpus() {
+        let solution = solve(&state, &options);
+        assert_eq!(solution.moves.len(), moves_left, "{state:?}");
+    }
+}
+
+#[test]
+fn safe_auto_play_keeps_minimum_solutions() {
+    for strategy in [SearchStrategy::AStar, SearchStrategy::IdaStar] {
+        let options = SolverOptions {
+            strategy,
+            auto_play: AutoPlay::Safe,
+            ..ADMISSIBLE_ASTAR
+        };
+        for (state, moves_left) in corpus() {
+            let solution = solve(&state, &options);
+            assert_eq!(solution.moves.len(), moves_left, "{state:?}");
+        }
+    }
+}
+
+#[test]
+fn portfolios_keep_minimum_solutions() {
+    for deterministic in [false, true] {
+        let options = SolverOptions {
+            parallelism: Parallelism::Portfolio {
+                searches: 4,
+                deterministic,
+            },
+            ..ADMISSIBLE_ASTAR
+        };
+        for (state, moves_left) in corpus() {
+            let solution = solve(&state, &options);
+            assert_eq!(solution.moves.len(), moves_left, "{state:?}");
+        }
+    }
+}
+
+#[test]
+fn admissible_weighted_a_star_is_bounded() {
+    let weight = 1.5;
+    let options = SolverOptions {
+        strategy: SearchStrategy::WeightedAStar { weight },
+        ..ADMISSIBLE_ASTAR
+    };
+    for (state, moves_left) in corpus() {
+        let solution = solve(&state, &options);
+        assert!(solution.moves.len() as f64 <= moves_left as f64 * weight);
+    }
+}
+
+#[test]
+fn admissible_heuristic_never_overestimates() {
+    for (state, _) in corpus() {
+        let solution = solve(&state, &ADMISSIBLE_ASTAR);
+        for (moves_left, state) in solution.states.iter().rev().enumerate() {
+            assert!(Heuristic::Admissible.estimate(state) <= moves_left);
+        }
+    }
+}
+
+#[test]
+fn solutions_replay_to_a_win() {
+    for (state, _) in corpus() {
+        let strategies = [
+            SearchStrategy::AStar,
+            SearchStrategy::WeightedAStar { weight: 2.0 },
+            SearchStrategy::IdaStar,
+            SearchStrategy::GreedyBestFirst,
+            SearchStrategy::DepthFirst,
+        ];
+        for ((strategy, heuristic), auto_play) in strategies
+            .into_iter()
+            .cartesian_product([Heuristic::Fast, Heuristic::Admissible])
+            .cartesian_product([AutoPlay::Off, AutoPlay::Safe])
+        {
+            let options = SolverOptions {
+                strategy,
+                heuristic,
+                auto_play,
+                ..Default::default()
+            };
+            let solution = solve(&state, &options);
+            let mut replayed = state.clone();
+            for (mv, expected) in solution.moves.iter().zip(&solution.states[1..]) {
+                replayed.apply(mv);
+                assert_eq!(&replayed, expected);
+            }
+            assert!(replayed.is_win());
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Checks that packing states loses nothing and keeps them apart.
+
+use std::collections::HashMap;
+
+use solitaire_solver::{
+    deck_generator::generate_game, packed::CompactPackedState, parser::read_from, state::State,
+};
+
+fn states() -> Vec<State> {
+    let mut states = vec![];
+    for seed in ["_3662067111875154814", "_1234", "42"] {
+        let start = generate_game(seed.parse().unwrap()).normalize();
+        states.extend(start.get_next_states().map(|(_, state)| state));
+        states.push(start);
+    }
+    for sample in [
+        include_str!("../samples/mid_game_sample.txt"),
+        include_str!("../samples/unsolvable_sample.txt"),
+    ] {
+        let start = read_from(sample.as_bytes()).unwrap().normalize();
+        let successors = start.get_next_states().map(|(_, state)| state);
+        states.extend(successors.flat_map(|state| {
+            let mut next = state
+                .get_next_states()
+                .map(|(_, state)| state)
+                .collect::<Vec<_>>();
+            next.push(state);
+            next
+        }));
+        states.push(start);
+    }
+    states
+}
+
+#[test]
+fn packing_round_trips() {
+    for state in states() {
+        assert_eq!(state.pack().unpack(), state);
+    }
+}
+
+#[test]
+fn packed_states_are_equal_exactly_when_states_are() {
+    let mut by_packed = HashMap::new();
+    for state in states() {
+        let previous = by_packed.insert(state.pack(), state.clone());
+        if let Some(previous) = previous {
+            assert_eq!(previous, state);
+        }
+    }
+}
+
+#[test]
+fn compact_packing_round_trips_and_keeps_states_apart() {
+    let mut by_packed = HashMap::new();
+    for state in states() {
+        let packed: CompactPackedState = state.pack_as();
+        assert_eq!(packed.unpack(), state);
+        if let Some(previous) = by_packed.insert(packed, state.clone()) {
+            assert_eq!(previous, state);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Reading deals from text, and how reading errors are reported.
+
+use solitaire_solver::{
+    parser::{ParseError, Suggestion, read_from},
+    state::{CardColor, CardNumber},
+};
+
+/// The position of `samples/mid_game_sample.txt` with the output line `output`.
+fn mid_game(output: &str) -> String {
+    format!(
+        "\
+slots: - p #w
+{output}
+
+9r 8r 7r 6r 5r 4r
+p p
+9t 8t 7t 6t 5t 4t
+p
+9b 8b 7b 6b 5b 4b
+s t
+"
+    )
+}
+
+#[test]
+fn output_colors_are_written_once() {
+    let state = read_from(mid_game("output: 3r 3t 3b").as_bytes()).unwrap();
+    assert_eq!(state.output[&CardColor::Red], CardNumber(3));
+    assert_eq!(state.output[&CardColor::Blue], CardNumber(3));
+
+    let error = read_from(mid_game("output: 2r 3t  3r 3b").as_bytes()).unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            ParseError::InvalidToken {
+                line: 2,
+                column: 16,
+                token,
+                suggestion: Suggestion(None),
+                ..
+            } if token == "3r"
+        ),
+        "{error}"
+    );
+}
+
+#[test]
+fn invalid_tokens_are_located_with_a_suggestion() {
+    let cases = [
+        ("9r 8r 7bb\n", 1, 7, "7bb", Some("7b")),
+        (
+            "9r 8r\n\n// a comment\n  9t\t8t r9 p\n",
+            4,
+            9,
+            "r9",
+            Some("9r"),
+        ),
+        ("slots: - #x p\n", 1, 10, "#x", Some("#w")),
+        ("output: 3r p\n", 1, 12, "p", None),
+        ("9r 8r // 7x\nbanana\n", 2, 1, "banana", None),
+        ("- 9r\n", 1, 3, "9r", None),
+    ];
+    for (text, line, column, token, suggestion) in cases {
+        match read_from(text.as_bytes()) {
+            Err(ParseError::InvalidToken {
+                line: found_line,
+                column: found_column,
+                token: found_token,
+                suggestion: Suggestion(found_suggestion),
+                ..
+            }) => assert_eq!(
+                (
+                    found_line,
+                    found_column,
+                    found_token.as_str(),
+                    found_suggestion.as_deref()
+                ),
+                (line, column, token, suggestion),
+                "{text:?}"
+            ),
+            other => panic!("{text:?}: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn parse_errors_name_the_line_and_column() {
+    let error = read_from("9r 8r\n9t  r9\n".as_bytes()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "line 2, column 5: invalid card `r9`, did you mean `9r`?"
+    );
+
+    let error = read_from("slots: - -\n".as_bytes()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "line 1: expected 3 placeholders, found 2"
+    );
+}
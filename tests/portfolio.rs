@@ -0,0 +1,90 @@
+//! Checks searches racing on several threads.
+
+use solitaire_solver::{
+    deck_generator::generate_game,
+    solver::{
+        Budget, Limit, Outcome, Parallelism, SearchStrategy, SolveResult, SolverOptions, solve_with,
+    },
+    state::State,
+};
+
+fn deals() -> impl Iterator<Item = State> {
+    ["1", "2", "_7", "12345"]
+        .into_iter()
+        .map(|seed| generate_game(seed.parse().unwrap()).normalize())
+}
+
+fn moves(result: &SolveResult) -> Vec<String> {
+    let solution = result.solution().expect("sample deals are solvable");
+    solution.moves.iter().map(|mv| format!("{mv:?}")).collect()
+}
+
+#[test]
+fn deterministic_portfolio_returns_the_requested_search() {
+    for strategy in [SearchStrategy::DepthFirst, SearchStrategy::GreedyBestFirst] {
+        let options = SolverOptions {
+            strategy,
+            ..Default::default()
+        };
+        let portfolio = SolverOptions {
+            parallelism: Parallelism::Portfolio {
+                searches: 6,
+                deterministic: true,
+            },
+            ..options
+        };
+        for deal in deals() {
+            let expected = moves(&solve_with(&deal, &options));
+            for _ in 0..3 {
+                assert_eq!(moves(&solve_with(&deal, &portfolio)), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn portfolio_solutions_replay_to_a_win() {
+    let options = SolverOptions {
+        strategy: SearchStrategy::GreedyBestFirst,
+        parallelism: Parallelism::Portfolio {
+            searches: 10,
+            deterministic: false,
+        },
+        ..Default::default()
+    };
+    for deal in deals() {
+        let result = solve_with(&deal, &options);
+        let solution = result.solution().expect("sample deals are solvable");
+        let mut replayed = deal.clone();
+        for (mv, expected) in solution.moves.iter().zip(&solution.states[1..]) {
+            replayed.apply(mv);
+            assert_eq!(&replayed, expected);
+        }
+        assert!(replayed.is_win());
+    }
+}
+
+#[test]
+fn portfolio_shares_the_node_budget() {
+    let options = SolverOptions {
+        budget: Budget {
+            expanded_nodes: Some(40),
+            ..Default::default()
+        },
+        parallelism: Parallelism::Portfolio {
+            searches: 4,
+            deterministic: true,
+        },
+        ..Default::default()
+    };
+    let deal = generate_game("_3662067111875154814".parse().unwrap()).normalize();
+    let result = solve_with(&deal, &options);
+    assert!(matches!(
+        result.outcome,
+        Outcome::BudgetExceeded {
+            limit: Limit::ExpandedNodes,
+            ..
+        }
+    ));
+    assert_eq!(result.statistics.expanded_nodes, 40);
+}
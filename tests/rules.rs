@@ -0,0 +1,527 @@
+//! Conformance of the move generator and the win check to the rules of the game, one table row
+//! per rule and edge case.
+
+use solitaire_solver::{
+    actions::{
+        CardSource::{PlaceHolder, Stack},
+        Move,
+    },
+    denormalized::DenormalizedState,
+    parser::read_from,
+    position::Position,
+    rules,
+};
+
+struct MoveCase {
+    rule: &'static str,
+    before: &'static str,
+    mv: Move,
+    /// The table once `mv` is played, or `None` if it is illegal.
+    after: Option<&'static str>,
+}
+
+const MOVE_CASES: &[MoveCase] = &[
+    MoveCase {
+        rule: "a 1 goes out first",
+        before: "5b 1r",
+        mv: Move::Output { from: Stack(0) },
+        after: Some(
+            "output: 1r
+            5b",
+        ),
+    },
+    MoveCase {
+        rule: "the next number of a color goes out",
+        before: "
+            output: 2t
+            3t",
+        mv: Move::Output { from: Stack(0) },
+        after: Some(
+            "output: 3t
+            -",
+        ),
+    },
+    MoveCase {
+        rule: "a number cannot skip the one below it",
+        before: "
+            output: 1t
+            3t",
+        mv: Move::Output { from: Stack(0) },
+        after: None,
+    },
+    MoveCase {
+        rule: "a buried card cannot go out",
+        before: "1r 5b",
+        mv: Move::Output { from: Stack(0) },
+        after: None,
+    },
+    MoveCase {
+        rule: "a card held in a slot goes out",
+        before: "
+            slots: - 1b -
+            -",
+        mv: Move::Output {
+            from: PlaceHolder(1),
+        },
+        after: Some(
+            "output: 1b
+            -",
+        ),
+    },
+    MoveCase {
+        rule: "a card stacks one lower on another color",
+        before: "
+            5r
+            6b",
+        mv: Move::StackToStack {
+            from: 0,
+            to: 1,
+            count: 1,
+        },
+        after: Some(
+            "-
+            6b 5r",
+        ),
+    },
+    MoveCase {
+        rule: "a card does not stack on its own color",
+        before: "
+            5r
+            6r",
+        mv: Move::StackToStack {
+            from: 0,
+            to: 1,
+            count: 1,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "a card only stacks one lower",
+        before: "
+            4r
+            6b",
+        mv: Move::StackToStack {
+            from: 0,
+            to: 1,
+            count: 1,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "any card goes on an empty column",
+        before: "
+            9r 5r
+            -",
+        mv: Move::StackToStack {
+            from: 0,
+            to: 1,
+            count: 1,
+        },
+        after: Some(
+            "9r
+            5r",
+        ),
+    },
+    MoveCase {
+        rule: "a pirate goes on an empty column",
+        before: "
+            9r p
+            -",
+        mv: Move::StackToStack {
+            from: 0,
+            to: 1,
+            count: 1,
+        },
+        after: Some(
+            "9r
+            p",
+        ),
+    },
+    MoveCase {
+        rule: "a pirate does not stack on a numbered card",
+        before: "
+            p
+            6b",
+        mv: Move::StackToStack {
+            from: 0,
+            to: 1,
+            count: 1,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "a numbered card does not stack on a beast",
+        before: "
+            5r
+            w",
+        mv: Move::StackToStack {
+            from: 0,
+            to: 1,
+            count: 1,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "a run moves together",
+        before: "
+            9t 6r 5b
+            7t",
+        mv: Move::StackToStack {
+            from: 0,
+            to: 1,
+            count: 2,
+        },
+        after: Some(
+            "9t
+            7t 6r 5b",
+        ),
+    },
+    MoveCase {
+        rule: "a run moves onto an empty column",
+        before: "
+            6r 5b
+            -",
+        mv: Move::StackToStack {
+            from: 0,
+            to: 1,
+            count: 2,
+        },
+        after: Some(
+            "-
+            6r 5b",
+        ),
+    },
+    MoveCase {
+        rule: "cards of the same color do not form a run",
+        before: "
+            6r 5r
+            7t",
+        mv: Move::StackToStack {
+            from: 0,
+            to: 1,
+            count: 2,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "an exposed card goes into an empty slot",
+        before: "9b 5r",
+        mv: Move::StackToPlaceHolder { from: 0, to: 1 },
+        after: Some(
+            "slots: - 5r -
+            9b",
+        ),
+    },
+    MoveCase {
+        rule: "a beast goes into an empty slot",
+        before: "w",
+        mv: Move::StackToPlaceHolder { from: 0, to: 0 },
+        after: Some(
+            "slots: w - -
+            -",
+        ),
+    },
+    MoveCase {
+        rule: "a slot holds a single card",
+        before: "
+            slots: p - -
+            5r",
+        mv: Move::StackToPlaceHolder { from: 0, to: 0 },
+        after: None,
+    },
+    MoveCase {
+        rule: "an empty column has no card to hold",
+        before: "-",
+        mv: Move::StackToPlaceHolder { from: 0, to: 0 },
+        after: None,
+    },
+    MoveCase {
+        rule: "a held card goes back onto a column",
+        before: "
+            slots: 5r - -
+            6b",
+        mv: Move::PlaceHolderToStack { from: 0, to: 0 },
+        after: Some("6b 5r"),
+    },
+    MoveCase {
+        rule: "a held card follows the stacking rule",
+        before: "
+            slots: 5r - -
+            6r",
+        mv: Move::PlaceHolderToStack { from: 0, to: 0 },
+        after: None,
+    },
+    MoveCase {
+        rule: "a bundle never leaves its slot",
+        before: "
+            slots: #w - -
+            -",
+        mv: Move::PlaceHolderToStack { from: 0, to: 0 },
+        after: None,
+    },
+    MoveCase {
+        rule: "a beast from a column is bundled into the first empty slot",
+        before: "
+            slots: 5r - -
+            w
+            p
+            p",
+        mv: Move::PromoteBeast {
+            beast: Stack(0),
+            pirates: [Stack(1), Stack(2)],
+            into: 1,
+        },
+        after: Some(
+            "slots: 5r #w -
+            -",
+        ),
+    },
+    MoveCase {
+        rule: "a beast from a column is not bundled into a later empty slot",
+        before: "
+            slots: 5r - -
+            w
+            p
+            p",
+        mv: Move::PromoteBeast {
+            beast: Stack(0),
+            pirates: [Stack(1), Stack(2)],
+            into: 2,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "a held beast is bundled into the slot it leaves",
+        before: "
+            slots: - - t
+            p
+            p",
+        mv: Move::PromoteBeast {
+            beast: PlaceHolder(2),
+            pirates: [Stack(0), Stack(1)],
+            into: 2,
+        },
+        after: Some(
+            "slots: - - #t
+            -",
+        ),
+    },
+    MoveCase {
+        rule: "a held beast is not bundled into another slot",
+        before: "
+            slots: - - t
+            p
+            p",
+        mv: Move::PromoteBeast {
+            beast: PlaceHolder(2),
+            pirates: [Stack(0), Stack(1)],
+            into: 0,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "a pirate frees the only slot for the bundle",
+        before: "
+            slots: 5r p 6b
+            s
+            p",
+        mv: Move::PromoteBeast {
+            beast: Stack(0),
+            pirates: [Stack(1), PlaceHolder(1)],
+            into: 1,
+        },
+        after: Some(
+            "slots: 5r #s 6b
+            -",
+        ),
+    },
+    MoveCase {
+        rule: "the bundle goes into the first slot the pirates leave",
+        before: "
+            slots: p p 5r
+            s",
+        mv: Move::PromoteBeast {
+            beast: Stack(0),
+            pirates: [PlaceHolder(0), PlaceHolder(1)],
+            into: 0,
+        },
+        after: Some(
+            "slots: #s - 5r
+            -",
+        ),
+    },
+    MoveCase {
+        rule: "the bundle does not go into the second slot the pirates leave",
+        before: "
+            slots: p p 5r
+            s",
+        mv: Move::PromoteBeast {
+            beast: Stack(0),
+            pirates: [PlaceHolder(0), PlaceHolder(1)],
+            into: 1,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "a beast cannot be promoted without a slot",
+        before: "
+            slots: 1r 2r 3r
+            w
+            p
+            p",
+        mv: Move::PromoteBeast {
+            beast: Stack(0),
+            pirates: [Stack(1), Stack(2)],
+            into: 0,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "a buried pirate cannot be bundled",
+        before: "
+            w
+            p
+            p 9r",
+        mv: Move::PromoteBeast {
+            beast: Stack(0),
+            pirates: [Stack(1), Stack(2)],
+            into: 0,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "a buried beast cannot be promoted",
+        before: "
+            w 9r
+            p
+            p",
+        mv: Move::PromoteBeast {
+            beast: Stack(0),
+            pirates: [Stack(1), Stack(2)],
+            into: 0,
+        },
+        after: None,
+    },
+    MoveCase {
+        rule: "a pirate is only bundled once",
+        before: "
+            w
+            p",
+        mv: Move::PromoteBeast {
+            beast: Stack(0),
+            pirates: [Stack(1), Stack(1)],
+            into: 0,
+        },
+        after: None,
+    },
+];
+
+struct WinCase {
+    rule: &'static str,
+    table: &'static str,
+    won: bool,
+}
+
+const WIN_CASES: &[WinCase] = &[
+    WinCase {
+        rule: "an empty table is won",
+        table: "
+            output: 9r 9t 9b
+            -",
+        won: true,
+    },
+    WinCase {
+        rule: "bundles stay in their slots",
+        table: "
+            slots: #w #t #s
+            output: 9r 9t 9b
+            -",
+        won: true,
+    },
+    WinCase {
+        rule: "a card left on a column is not won",
+        table: "
+            slots: #w #t #s
+            output: 8r 9t 9b
+            9r",
+        won: false,
+    },
+    WinCase {
+        rule: "a numbered card left in a slot is not won",
+        table: "
+            slots: #w 9r #t
+            output: 8r 9t 9b
+            -",
+        won: false,
+    },
+    WinCase {
+        rule: "a pirate left in a slot is not won",
+        table: "
+            slots: #w p #t
+            output: 9r 9t 9b
+            -",
+        won: false,
+    },
+];
+
+/// Reads `text` as the parser does, with missing columns left empty.
+fn table(text: &str) -> DenormalizedState {
+    let columns = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            !line.is_empty() && !line.starts_with("slots:") && !line.starts_with("output:")
+        })
+        .count();
+    let text = format!("{text}\n{}", "-\n".repeat(6 - columns));
+    read_from(text.as_bytes()).unwrap()
+}
+
+#[test]
+fn moves_follow_the_rules() {
+    for case in MOVE_CASES {
+        let before = table(case.before);
+        let legal = before.legal_moves().any(|mv| mv == case.mv);
+        assert_eq!(legal, case.after.is_some(), "{}", case.rule);
+
+        if let Some(after) = case.after {
+            let after = table(after);
+            let mut played = before.clone();
+            played.apply(&case.mv);
+            assert_eq!(played, after, "{}", case.rule);
+
+            let state = before.normalize();
+            assert!(
+                state
+                    .get_next_states()
+                    .any(|(mv, next)| next == after.normalize()
+                        && before.translate(&state, &mv) == case.mv),
+                "{}",
+                case.rule
+            );
+        }
+    }
+}
+
+#[test]
+fn normalized_states_and_positions_play_every_move_alike() {
+    for case in MOVE_CASES {
+        let state = table(case.before).normalize();
+        let mut position = Position::from(&state);
+        for (mv, next) in state.get_next_states() {
+            let undo = position.apply(&mv);
+            assert_eq!(position.to_state(), next, "{}: {mv:?}", case.rule);
+            position.undo(undo);
+            assert_eq!(position.to_state(), state, "{}: {mv:?}", case.rule);
+        }
+    }
+}
+
+#[test]
+fn wins_follow_the_rules() {
+    for case in WIN_CASES {
+        let table = table(case.table);
+        assert_eq!(rules::is_win(&table), case.won, "{}", case.rule);
+        assert_eq!(table.normalize().is_win(), case.won, "{}", case.rule);
+    }
+}
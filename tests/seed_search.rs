@@ -0,0 +1,267 @@
+//! Checks that seed scans report exactly the seeds meeting their filter, and find the seed of a
+//! deal.
+
+use std::{ops::ControlFlow, sync::Mutex};
+
+use solitaire_solver::{
+    batch::SeedRange,
+    config::GameConfig,
+    deck_generator::{generate_game, generate_game_with},
+    denormalized::DenormalizedState,
+    godot_shuffle::{Seed, shuffle, shuffles_into},
+    parser::{read_from, read_from_with},
+    seed_search::{SeedFilter, SeedMatch, dealt_deck, find_seeds, reverse_seed},
+    solver::{Heuristic, SolverOptions, solve_with},
+    state::Card,
+};
+
+/// A variant small enough to search every deal optimally in a test.
+const SMALL: GameConfig = GameConfig {
+    stacks: 5,
+    slots: 3,
+    ranks: 4,
+    colors: 2,
+    pirates: 2,
+};
+
+fn scan(range: &str, filter: &SeedFilter, options: &SolverOptions) -> Vec<SeedMatch> {
+    let found = Mutex::new(vec![]);
+    let summary = find_seeds(
+        &range.parse::<SeedRange>().unwrap(),
+        &options.game,
+        filter,
+        |state| solve_with(state, options),
+        |found_seed| {
+            found.lock().unwrap().push(found_seed);
+            Ok(ControlFlow::Continue(()))
+        },
+    )
+    .unwrap();
+    let mut found = found.into_inner().unwrap();
+    assert_eq!(summary.matched, found.len());
+    assert_eq!(summary.undecided, 0);
+    found.sort_by_key(|found| found.seed);
+    found
+}
+
+#[test]
+fn layout_filters_select_the_deals() {
+    let options = SolverOptions {
+        game: SMALL,
+        ..Default::default()
+    };
+    let filter = SeedFilter {
+        pirates_together: true,
+        min_beast_depth: Some(2),
+        ..Default::default()
+    };
+    let found = scan("_500..=500", &filter, &options);
+    assert!(!found.is_empty());
+
+    for seed in "_500..=500".parse::<SeedRange>().unwrap().seeds() {
+        let state = generate_game_with(seed, &SMALL);
+        let pirates_together = state.board.iter().any(|stack| {
+            stack
+                .cards
+                .iter()
+                .filter(|card| **card == Card::Pirate)
+                .count()
+                == SMALL.pirates
+        });
+        let beast_depth = state
+            .board
+            .iter()
+            .find_map(|stack| {
+                let position = stack
+                    .cards
+                    .iter()
+                    .position(|card| matches!(card, Card::Beast(_)))?;
+                Some(stack.cards.len() - 1 - position)
+            })
+            .unwrap();
+        let expected = pirates_together && beast_depth >= 2;
+        let reported = found.iter().any(|found| found.seed == seed);
+        assert_eq!(reported, expected, "seed {seed}");
+    }
+    assert!(
+        found
+            .iter()
+            .all(|found| found.solvable.is_none() && found.length.is_none())
+    );
+}
+
+#[test]
+fn length_filters_compare_the_fewest_moves() {
+    let options = SolverOptions {
+        game: SMALL,
+        heuristic: Heuristic::Admissible,
+        ..Default::default()
+    };
+    let filter = SeedFilter {
+        min_length: Some(11),
+        max_length: Some(12),
+        ..Default::default()
+    };
+    let found = scan("0..30", &filter, &options);
+    assert!(!found.is_empty());
+
+    for seed in (0..30).map(Seed::from) {
+        let state = generate_game_with(seed, &SMALL).normalize();
+        let length = solve_with(&state, &options)
+            .solution()
+            .map(|solution| solution.moves.len());
+        let expected = length.is_some_and(|length| (11..=12).contains(&length));
+        match found.iter().find(|found| found.seed == seed) {
+            Some(found) => {
+                assert!(expected, "seed {seed}");
+                assert_eq!(found.length, length);
+                assert_eq!(found.solvable, Some(true));
+            }
+            None => assert!(!expected, "seed {seed}"),
+        }
+    }
+}
+
+#[test]
+fn unsolvable_deals_are_found() {
+    let cramped = GameConfig {
+        stacks: 3,
+        slots: 0,
+        ranks: 6,
+        colors: 3,
+        pirates: 2,
+    };
+    let options = SolverOptions {
+        game: cramped,
+        ..Default::default()
+    };
+    let filter = SeedFilter {
+        unsolvable: true,
+        ..Default::default()
+    };
+    let found = scan("0..5", &filter, &options);
+    assert!(!found.is_empty());
+    assert!(found.iter().all(|found| found.solvable == Some(false)));
+}
+
+#[test]
+fn scans_stop_once_enough_seeds_are_found() {
+    let found = Mutex::new(vec![]);
+    let summary = find_seeds(
+        &"0..1000000".parse::<SeedRange>().unwrap(),
+        &GameConfig::STANDARD,
+        &SeedFilter::default(),
+        |_| unreachable!("layout filters need no search"),
+        |found_seed| {
+            let mut found = found.lock().unwrap();
+            found.push(found_seed);
+            Ok(if found.len() == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            })
+        },
+    )
+    .unwrap();
+    assert!(found.into_inner().unwrap().len() >= 3);
+    assert!(summary.scanned < 1000000);
+}
+
+#[test]
+fn matches_serialize_seeds_as_the_game_writes_them() {
+    let found = SeedMatch {
+        seed: Seed::from(-20),
+        solvable: Some(true),
+        length: Some(10),
+    };
+    let json = serde_json::to_string(&found).unwrap();
+    assert_eq!(json, r#"{"seed":"_20","solvable":true,"length":10}"#);
+    assert_eq!(serde_json::from_str::<SeedMatch>(&json).unwrap(), found);
+}
+
+/// Writes the columns of `state` one per line, as card files do.
+fn cards_file(state: &DenormalizedState) -> String {
+    state
+        .board
+        .iter()
+        .map(|stack| {
+            stack
+                .cards
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn seeds_are_recovered_from_their_deal() {
+    for seed in [-3662067111875154814, -7, 0, 42, i64::MAX] {
+        let text = cards_file(&generate_game(Seed::from(seed)));
+        let state = read_from(text.as_bytes()).unwrap();
+        let range = format!(
+            "{}..={}",
+            Seed::from(seed.saturating_sub(5000)),
+            Seed::from(seed.saturating_add(5000))
+        );
+        let found = reverse_seed(
+            &state,
+            &range.parse().unwrap(),
+            &GameConfig::STANDARD,
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(found, Some(Seed::from(seed)));
+    }
+
+    let state = generate_game_with(Seed::from(-300), &SMALL);
+    let state = read_from_with(cards_file(&state).as_bytes(), &SMALL).unwrap();
+    let found = reverse_seed(&state, &"_1000..1000".parse().unwrap(), &SMALL, |_| {}).unwrap();
+    assert_eq!(found, Some(Seed::from(-300)));
+}
+
+#[test]
+fn seeds_outside_the_range_are_not_found() {
+    let state = generate_game(Seed::from(5000));
+    let progress = Mutex::new(vec![]);
+    let found = reverse_seed(
+        &state,
+        &"0..5000".parse().unwrap(),
+        &GameConfig::STANDARD,
+        |scanned| progress.lock().unwrap().push(scanned),
+    )
+    .unwrap();
+    assert_eq!(found, None);
+    assert_eq!(progress.into_inner().unwrap().last(), Some(&5000));
+}
+
+#[test]
+fn deals_in_progress_cannot_be_reversed() {
+    let mut state = generate_game(Seed::from(42));
+    let mv = state.legal_moves().next().unwrap();
+    state.apply(&mv);
+    assert!(dealt_deck(&state, &GameConfig::STANDARD).is_err());
+}
+
+#[test]
+fn early_stopping_shuffles_agree_with_the_full_shuffle() {
+    let deck = GameConfig::STANDARD.deck();
+    for seed in (-200..200).map(Seed::from) {
+        let mut target = deck.clone();
+        shuffle(&mut target, seed);
+        assert_eq!(
+            dealt_deck(&generate_game(seed), &GameConfig::STANDARD).unwrap(),
+            target
+        );
+        for other in [seed, Seed::from(i64::from(seed) + 1)] {
+            let mut shuffled = deck.clone();
+            assert_eq!(
+                shuffles_into(&mut shuffled, other, &target),
+                other == seed,
+                "{seed} and {other}"
+            );
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Checks that invalid states are reported with every inconsistency they contain.
+
+use solitaire_solver::{
+    parser::read_from,
+    state::{Card, CardColor, CardNumber, CardStack, NormalCard},
+    validators::InvalidState,
+};
+
+fn card(number: u8, color: CardColor) -> Card {
+    Card::Normal(NormalCard {
+        number: CardNumber(number),
+        color,
+    })
+}
+
+#[test]
+fn every_inconsistency_is_reported_at_once() {
+    // 4t replaced by a second 9r, a green card and a green output in the standard game
+    let mut state = read_from(
+        "\
+        slots: - p #w
+        output: 3r 3t 3b 1g
+
+        9r 8r 7r 6r 5r 4r
+        p p 1g
+        9t 8t 7t 6t 5t 9r
+        p
+        9b 8b 7b 6b 5b 4b
+        s t
+        "
+        .as_bytes(),
+    )
+    .unwrap();
+    state.board.push(CardStack { cards: vec![] });
+
+    let errors = state.normalize().is_valid().unwrap_err().0;
+    let expected = [
+        InvalidState::StackCount {
+            count: 7,
+            expected: 6,
+        },
+        InvalidState::InvalidOutput {
+            color: CardColor::Green,
+            number: CardNumber(1),
+        },
+        InvalidState::UnknownCard {
+            card: card(1, CardColor::Green),
+        },
+        InvalidState::InvalidCardAmount {
+            card: card(4, CardColor::Teal),
+            count: 0,
+            expected: 1,
+        },
+        InvalidState::InvalidCardAmount {
+            card: card(9, CardColor::Red),
+            count: 2,
+            expected: 1,
+        },
+    ];
+    for error in &expected {
+        assert!(errors.contains(error), "{error} missing from {errors:?}");
+    }
+    assert_eq!(errors.len(), expected.len(), "{errors:?}");
+}
@@ -0,0 +1,148 @@
+//! Checks that variants of the game are dealt, read, validated and solved like the standard one.
+
+use solitaire_solver::{
+    config::{ConfigError, GameConfig},
+    deck_generator::{generate_game, generate_game_with},
+    parser::{ParseError, read_from_with},
+    position::Position,
+    solver::{AutoPlay, SearchStrategy, SolverOptions, solve_with},
+    validators::InvalidState,
+};
+
+const HARDER: GameConfig = GameConfig {
+    stacks: 8,
+    slots: 2,
+    ranks: 9,
+    colors: 4,
+    pirates: 6,
+};
+
+const EASIER: GameConfig = GameConfig {
+    stacks: 5,
+    slots: 3,
+    ranks: 6,
+    colors: 2,
+    pirates: 2,
+};
+
+#[test]
+fn the_standard_config_deals_the_game() {
+    for seed in ["_3662067111875154814", "42", "7"] {
+        let seed = seed.parse().unwrap();
+        assert_eq!(
+            generate_game_with(seed, &GameConfig::STANDARD),
+            generate_game(seed)
+        );
+    }
+}
+
+#[test]
+fn variants_deal_their_whole_deck() {
+    for config in [HARDER, EASIER] {
+        config.validate().unwrap();
+        let state = generate_game_with("42".parse().unwrap(), &config);
+        assert_eq!(state.board.len(), config.stacks);
+        assert_eq!(state.placeholders.holes.len(), config.slots);
+        let cards = state
+            .board
+            .iter()
+            .map(|stack| stack.cards.len())
+            .sum::<usize>();
+        assert_eq!(cards, config.card_count());
+        state.normalize().is_valid_with(&config).unwrap();
+        assert!(state.normalize().is_valid().is_err());
+    }
+}
+
+#[test]
+fn variants_are_solved_and_hashed_like_the_game() {
+    let wider = GameConfig {
+        ranks: 4,
+        pirates: 4,
+        ..HARDER
+    };
+    for (config, auto_play) in [(EASIER, AutoPlay::Off), (wider, AutoPlay::Safe)] {
+        let start = generate_game_with("42".parse().unwrap(), &config).normalize();
+        let options = SolverOptions {
+            game: config,
+            strategy: SearchStrategy::DepthFirst,
+            auto_play,
+            ..Default::default()
+        };
+        let solution = solve_with(&start, &options);
+        let solution = solution.solution().expect("the deal should be solvable");
+
+        for (window, mv) in solution.states.windows(2).zip(&solution.moves) {
+            let [state, next] = window else {
+                unreachable!()
+            };
+            assert_eq!(state.pack().unpack(), *state);
+            let mut position = Position::from(state);
+            assert_eq!(position.zobrist_hash(), state.zobrist_hash());
+            position.apply(mv);
+            assert_eq!(position.to_state(), *next);
+        }
+        assert!(solution.states.last().unwrap().is_win());
+    }
+}
+
+#[test]
+fn card_files_follow_the_variant_table() {
+    let text = "
+        slots: - 7g
+        output: 1g
+        1t 2g
+        -
+        -
+        -
+        -
+        -
+        -
+        -
+    ";
+    let state = read_from_with(text.as_bytes(), &HARDER).unwrap();
+    assert_eq!(state.board.len(), 8);
+    assert!(matches!(
+        read_from_with(text.as_bytes(), &EASIER),
+        Err(ParseError::PlaceHolderCount {
+            expected: 3,
+            found: 2,
+            ..
+        })
+    ));
+
+    let errors = state.normalize().is_valid_with(&EASIER).unwrap_err().0;
+    assert!(errors.contains(&InvalidState::StackCount {
+        count: 8,
+        expected: 5
+    }));
+}
+
+#[test]
+fn unsupported_variants_are_rejected() {
+    let odd = GameConfig {
+        pirates: 5,
+        ..GameConfig::STANDARD
+    };
+    assert_eq!(odd.validate(), Err(ConfigError::OddPirates(5)));
+
+    let too_many_ranks = GameConfig {
+        ranks: 10,
+        ..GameConfig::STANDARD
+    };
+    assert!(matches!(
+        too_many_ranks.validate(),
+        Err(ConfigError::OutOfRange { what: "ranks", .. })
+    ));
+
+    let too_large = GameConfig {
+        stacks: 15,
+        slots: 7,
+        colors: 4,
+        ..GameConfig::STANDARD
+    };
+    assert!(matches!(
+        too_large.validate(),
+        Err(ConfigError::TooLarge { .. })
+    ));
+}
@@ -0,0 +1,191 @@
+//! Checks the replay of written solutions.
+
+use rand::{SeedableRng, seq::IndexedRandom};
+use rand_pcg::Pcg64;
+use solitaire_solver::{
+    actions::{CardSource, Move},
+    deck_generator::generate_game,
+    denormalized::DenormalizedState,
+    game::Spot,
+    parser::read_from,
+    solver::{SearchStrategy, SolverOptions, solve_with},
+    state::Beast,
+    verify::{MoveError, VerifyError, verify},
+};
+
+fn deal(seed: &str) -> DenormalizedState {
+    generate_game(seed.parse().unwrap())
+}
+
+fn written_solution(start: &DenormalizedState) -> String {
+    let options = SolverOptions {
+        strategy: SearchStrategy::DepthFirst,
+        ..Default::default()
+    };
+    let result = solve_with(&start.normalize(), &options);
+    let line = start.replay(result.solution().unwrap());
+    line.states
+        .iter()
+        .zip(&line.moves)
+        .enumerate()
+        .map(|(i, (state, mv))| format!("{:>3}. {}\n", i + 1, state.describe(*mv)))
+        .collect()
+}
+
+fn first_error(start: &DenormalizedState, solution: &str) -> (usize, MoveError) {
+    match verify(start, solution.as_bytes()) {
+        Err(VerifyError::IllegalMove(illegal)) => (illegal.number, illegal.error),
+        result => panic!("expected an illegal move, got {result:?}"),
+    }
+}
+
+#[test]
+fn every_legal_move_reads_back_from_its_description() {
+    let mut rng = Pcg64::seed_from_u64(0);
+    for seed in ["1", "_7", "12345", "_3662067111875154814"] {
+        let mut state = deal(seed);
+        for _ in 0..150 {
+            let moves = state.legal_moves().collect::<Vec<_>>();
+            for mv in &moves {
+                let written = state.describe(*mv).to_string();
+                assert_eq!(state.parse_move(&written), Ok(*mv), "{written}");
+            }
+            let Some(mv) = moves.choose(&mut rng) else {
+                break;
+            };
+            state.apply(mv);
+        }
+    }
+}
+
+#[test]
+fn solver_solutions_verify() {
+    for seed in ["1", "2", "_7", "12345"] {
+        let start = deal(seed);
+        let replay = verify(&start, written_solution(&start).as_bytes()).unwrap();
+        assert!(replay.is_won());
+    }
+}
+
+#[test]
+fn unfinished_solutions_are_not_won() {
+    let start = deal("12345");
+    let solution = written_solution(&start);
+    let first_moves = solution.lines().take(10).collect::<Vec<_>>().join("\n");
+    let replay = verify(&start, first_moves.as_bytes()).unwrap();
+    assert_eq!(replay.moves.len(), 10);
+    assert!(!replay.is_won());
+}
+
+#[test]
+fn comments_blank_lines_and_numbers_are_optional() {
+    let start = deal("_3662067111875154814");
+    let solution = "\
+        // shared by a teammate
+        move 8t from column 5 to column 3
+
+        2. move w from column 5 to slot 1 // keep the whale
+        promote whale with pirates from column 4 and column 5
+    ";
+    let replay = verify(&start, solution.as_bytes()).unwrap();
+    assert_eq!(replay.moves.len(), 3);
+}
+
+#[test]
+fn illegal_moves_are_explained() {
+    let start = deal("_3662067111875154814");
+    let cases = [
+        ("frobnicate the whale", MoveError::Unreadable),
+        (
+            "move 2r (+18446744073709551615) from column 2 to column 1",
+            MoveError::Unreadable,
+        ),
+        (
+            "move 8t from column 7 to column 3",
+            MoveError::NoSuchPlace(Spot::Column(6)),
+        ),
+        (
+            "move 9t from column 5 to column 3",
+            MoveError::WrongCard {
+                place: Spot::Column(4),
+                found: "8t".to_string(),
+                expected: "9t".to_string(),
+            },
+        ),
+        (
+            "move 8t from column 5 to column 5",
+            MoveError::SamePlace(Spot::Column(4)),
+        ),
+        (
+            "move 3b from slot 1 to column 2",
+            MoveError::Empty(Spot::Slot(0)),
+        ),
+        (
+            "send 8t from column 5 to the output",
+            MoveError::OutputOutOfOrder {
+                card: "8t".to_string(),
+                needed: "1t".to_string(),
+            },
+        ),
+        (
+            "move 3b from column 1 to column 2",
+            MoveError::CannotStack {
+                card: "3b".to_string(),
+                below: "7b".to_string(),
+                place: Spot::Column(1),
+            },
+        ),
+    ];
+    for (written, expected) in cases {
+        assert_eq!(first_error(&start, written), (1, expected), "{written}");
+    }
+
+    let solution = "\
+        move 8t from column 5 to column 3
+        move w from column 5 to slot 1
+        move p from column 5 to slot 1
+    ";
+    assert_eq!(
+        first_error(&start, solution),
+        (3, MoveError::SlotTaken(Spot::Slot(0)))
+    );
+}
+
+#[test]
+fn promotions_name_their_beast_when_several_are_exposed() {
+    let start = read_from(
+        "\
+        slots: - - -
+        output: 9r 9t 9b
+
+        w
+        w
+        p
+        p
+        -
+        -
+        "
+        .as_bytes(),
+    )
+    .unwrap();
+    assert_eq!(
+        start.parse_move("promote whale with pirates from column 3 and column 4"),
+        Err(MoveError::AmbiguousBeast(Beast::Whale))
+    );
+    assert_eq!(
+        start.parse_move("promote whale from column 2 with pirates from column 4 and column 3"),
+        Ok(Move::PromoteBeast {
+            beast: CardSource::Stack(1),
+            pirates: [CardSource::Stack(2), CardSource::Stack(3)],
+            into: 0,
+        })
+    );
+    assert_eq!(
+        start.parse_move("promote ship with pirates from column 3 and column 4"),
+        Err(MoveError::NoExposedBeast(Beast::Ship))
+    );
+    assert_eq!(
+        start.parse_move("promote whale from column 1 with pirates from column 3 and column 3"),
+        Err(MoveError::SamePirate)
+    );
+}
@@ -0,0 +1,75 @@
+//! Checks that positions keep their Zobrist hash up to date through moves and undos.
+
+use rand::{Rng, SeedableRng, seq::IndexedRandom};
+use rand_pcg::Pcg64;
+use solitaire_solver::{deck_generator::generate_game, position::Position, state::State};
+
+const SEEDS: [&str; 4] = ["_3662067111875154814", "_1234", "42", "7"];
+
+fn starts() -> impl Iterator<Item = State> {
+    SEEDS
+        .into_iter()
+        .map(|seed| generate_game(seed.parse().unwrap()).normalize())
+}
+
+#[test]
+fn random_walks_keep_the_hash_and_undo_exactly() {
+    let mut rng = Pcg64::seed_from_u64(0);
+    for start in starts() {
+        let mut position = Position::from(&start);
+        assert_eq!(position.zobrist_hash(), start.zobrist_hash());
+        let mut undos = vec![];
+        let mut states = vec![start.clone()];
+        for _ in 0..200 {
+            let moves = position.legal_moves();
+            let Some(mv) = moves.choose(&mut rng) else {
+                break;
+            };
+            let mut expected = states.last().unwrap().clone();
+            expected.apply(&position.normalize_move(mv));
+
+            undos.push(position.apply(mv));
+            let state = position.to_state();
+            assert_eq!(state, expected);
+            assert_eq!(position.zobrist_hash(), state.zobrist_hash());
+            assert_eq!(position.pack(), state.pack());
+            states.push(state);
+
+            if rng.random_bool(0.3) {
+                position.undo(undos.pop().unwrap());
+                states.pop();
+                assert_eq!(&position.to_state(), states.last().unwrap());
+                assert_eq!(
+                    position.zobrist_hash(),
+                    states.last().unwrap().zobrist_hash()
+                );
+            }
+        }
+        while let Some(undo) = undos.pop() {
+            position.undo(undo);
+        }
+        assert_eq!(position.to_state(), start);
+        assert_eq!(position.zobrist_hash(), start.zobrist_hash());
+    }
+}
+
+#[test]
+fn moves_are_generated_in_normalized_order() {
+    let mut rng = Pcg64::seed_from_u64(1);
+    for start in starts() {
+        let mut position = Position::from(&start);
+        for _ in 0..100 {
+            let moves = position.legal_moves();
+            let state = position.to_state();
+            let normalized = moves
+                .iter()
+                .map(|mv| position.normalize_move(mv))
+                .collect::<Vec<_>>();
+            assert_eq!(normalized, state.legal_moves().collect::<Vec<_>>());
+            let Some(mv) = moves.choose(&mut rng) else {
+                break;
+            };
+            position.apply(mv);
+        }
+    }
+}